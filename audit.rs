@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::process::Command as ProcessCommand;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::checkpoint::unix_now;
+use crate::geometry::{self, SectorGeometry};
+use crate::identity::{self, DeviceIdentity};
+use crate::residue::{self, ResidueOutcome, ScanMode};
+use crate::verify::{self, MIN_RANDOM_SEGMENT, SAMPLE_SIZE};
+use crate::write_engine::AlignedBuffer;
+
+const MAX_REPORTED_SEGMENTS: usize = 32;
+
+/// A partition table or filesystem signature that `wipefs` still finds.
+#[derive(Debug, Clone)]
+pub struct FoundSignature {
+    pub offset: String,
+    pub kind: String,
+}
+
+/// Sampled 64 KiB segment that is neither a uniform fill nor random.
+#[derive(Debug, Clone)]
+pub struct DataSegment {
+    pub offset: u64,
+    pub entropy: f64,
+    pub chi_square: f64,
+}
+
+/// What the samples of a supposedly wiped drive contain.
+#[derive(Debug, Clone)]
+pub struct SampleSurvey {
+    pub seed: u64,
+    pub samples: u64,
+    pub bytes_read: u64,
+    pub bytes_unreadable: u64,
+    pub target_size: u64,
+    /// Segments holding a single repeated byte, by byte value
+    pub fills: BTreeMap<u8, u64>,
+    pub random: u64,
+    pub data: Vec<DataSegment>,
+    pub data_segments: u64,
+}
+
+impl SampleSurvey {
+    pub fn describe(&self) -> String {
+        let coverage = if self.target_size == 0 { 0.0 } else { self.bytes_read as f64 * 100.0 / self.target_size as f64 };
+        let fills: Vec<String> = self.fills.iter().map(|(byte, count)| format!("{} x 0x{:02x} fill", count, byte)).collect();
+        let mut summary = format!("{} samples, {:.3}% of the device read (sample seed {}): {} random, {}, {} with data",
+            self.samples, coverage, self.seed, self.random,
+            if fills.is_empty() { "0 filled".to_string() } else { fills.join(", ") },
+            self.data_segments);
+        if self.bytes_unreadable > 0 {
+            summary.push_str(&format!(", {} bytes unreadable", self.bytes_unreadable));
+        }
+        summary
+    }
+}
+
+/// Evidence gathered by an audit; the verdict is derived from it alone.
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub device: String,
+    pub identity: DeviceIdentity,
+    pub geometry: SectorGeometry,
+    pub signatures: Result<Vec<FoundSignature>, String>,
+    pub survey: SampleSurvey,
+    pub residue: ResidueOutcome,
+    pub started: u64,
+    pub finished: u64,
+}
+
+impl AuditRecord {
+    /// Reasons the drive cannot be accepted as wiped; empty means pass.
+    pub fn findings(&self) -> Vec<String> {
+        let mut findings = Vec::new();
+        match &self.signatures {
+            Ok(found) if !found.is_empty() => findings.push(format!("{} partition table / filesystem signatures present", found.len())),
+            Ok(_) => {}
+            Err(e) => findings.push(format!("signature probe failed: {}", e)),
+        }
+        if self.survey.data_segments > 0 {
+            findings.push(format!("{} sampled segments hold structured data", self.survey.data_segments));
+        }
+        if !self.residue.clean() {
+            findings.push(format!("{} file signatures found by the residue scan", self.residue.total_hits));
+        }
+        if let Some(hpa) = &self.residue.hidden_area {
+            findings.push(format!("host protected area hides sectors that cannot be audited ({})", hpa));
+        }
+        findings
+    }
+
+    pub fn passed(&self) -> bool {
+        self.findings().is_empty()
+    }
+}
+
+/// Partition tables and filesystems found by `wipefs --no-act`, which only
+/// lists signatures and never erases them.
+fn probe_signatures(device: &str) -> io::Result<Vec<FoundSignature>> {
+    let output = ProcessCommand::new("wipefs")
+        .args(["--no-act", "--noheadings", "--output", "OFFSET,TYPE", device])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(
+            format!("wipefs failed: {}", String::from_utf8_lossy(&output.stderr).trim())
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(FoundSignature { offset: fields.next()?.to_string(), kind: fields.next()?.to_string() })
+        })
+        .collect())
+}
+
+/// Read stratified samples and sort every 64 KiB segment into uniform fill,
+/// random (ciphertext or a random pass) or data, without assuming which
+/// wipe method was used.
+fn survey_samples(device: &str, coverage_percent: f64, seed: u64) -> io::Result<SampleSurvey> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(device)?;
+    let size = file.seek(SeekFrom::End(0))?;
+    let geometry = geometry::read_geometry(device).unwrap_or_default();
+    let mut buffer = AlignedBuffer::zeroed(SAMPLE_SIZE as usize, geometry.logical as usize);
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let offsets = verify::sample_offsets(size, coverage_percent, &mut rng);
+    let mut survey = SampleSurvey {
+        seed,
+        samples: offsets.len() as u64,
+        bytes_read: 0,
+        bytes_unreadable: 0,
+        target_size: size,
+        fills: BTreeMap::new(),
+        random: 0,
+        data: Vec::new(),
+        data_segments: 0,
+    };
+
+    for (i, &offset) in offsets.iter().enumerate() {
+        let len = SAMPLE_SIZE.min(size - offset) as usize;
+        let data = &mut buffer.as_mut_slice()[..len];
+        match verify::read_sample(&file, data, offset) {
+            Ok(()) => survey.bytes_read += len as u64,
+            Err(e) if e.raw_os_error() == Some(libc::EIO) => {
+                survey.bytes_unreadable += len as u64;
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+
+        for (j, segment) in data.chunks(MIN_RANDOM_SEGMENT).enumerate() {
+            if segment.iter().all(|&b| b == segment[0]) {
+                *survey.fills.entry(segment[0]).or_insert(0) += 1;
+                continue;
+            }
+            let (entropy, chi_square, random) = verify::randomness(segment);
+            // A short tail segment is too small to judge; only uniform fills count there
+            if random || segment.len() < MIN_RANDOM_SEGMENT {
+                survey.random += 1;
+            } else {
+                survey.data_segments += 1;
+                if survey.data.len() < MAX_REPORTED_SEGMENTS {
+                    survey.data.push(DataSegment { offset: offset + (j * MIN_RANDOM_SEGMENT) as u64, entropy, chi_square });
+                }
+            }
+        }
+
+        if i % 16 == 0 || i + 1 == offsets.len() {
+            print!("\r   Sampled {} / {} ({} segments with data)   ", i + 1, offsets.len(), survey.data_segments);
+            io::stdout().flush()?;
+        }
+    }
+    println!();
+    Ok(survey)
+}
+
+/// Audit a drive that is claimed to have been wiped. Everything is read-only:
+/// the device is only ever opened for reading and the external tools used
+/// (wipefs --no-act, hdparm -N without a value) do not modify it.
+pub fn audit_device(device: &str, coverage_percent: f64, mode: ScanMode, seed: u64) -> io::Result<AuditRecord> {
+    let started = unix_now();
+    let identity = identity::read_identity(device);
+    let geometry = geometry::read_geometry(device).unwrap_or_default();
+
+    println!("🔎 Auditing {} (read-only)...", device);
+    println!("🏷️  Probing partition table and filesystem signatures...");
+    let signatures = probe_signatures(device).map_err(|e| e.to_string());
+
+    println!("🎯 Sampling the device...");
+    let survey = survey_samples(device, coverage_percent, seed)?;
+
+    println!("🧬 Scanning for leftover file signatures ({})...", mode.name());
+    let mut residue = residue::scan(device, mode, coverage_percent, seed, 0)?;
+    residue.hidden_area = residue::hidden_area(device);
+
+    Ok(AuditRecord {
+        device: device.to_string(),
+        identity,
+        geometry,
+        signatures,
+        survey,
+        residue,
+        started,
+        finished: unix_now(),
+    })
+}
+
+pub fn print_audit_report(record: &AuditRecord) {
+    let _ = write_audit_report(record, &mut io::stdout());
+}
+
+pub fn write_audit_report(record: &AuditRecord, out: &mut impl Write) -> io::Result<()> {
+    let separator = "═".repeat(60);
+    let findings = record.findings();
+    writeln!(out, "\n{}", separator)?;
+    writeln!(out, "📋 WIPE AUDIT REPORT")?;
+    writeln!(out, "{}", separator)?;
+    writeln!(out, "📱 Device: {}", record.device)?;
+    writeln!(out, "🏷️  Drive: {} (serial {}, {} bytes)",
+        record.identity.model, record.identity.serial, record.identity.size_bytes)?;
+    writeln!(out, "💽 Sectors: {}", record.geometry.describe())?;
+    writeln!(out, "🔒 Access: read-only, the device was never opened for writing")?;
+
+    match &record.signatures {
+        Ok(found) if found.is_empty() => writeln!(out, "🗂️  Partition/filesystem signatures: none"),
+        Ok(found) => {
+            writeln!(out, "🗂️  Partition/filesystem signatures:")?;
+            for signature in found {
+                writeln!(out, "   ❌ {} at offset {}", signature.kind, signature.offset)?;
+            }
+            Ok(())
+        }
+        Err(e) => writeln!(out, "🗂️  Partition/filesystem signatures: not probed ({})", e),
+    }?;
+
+    writeln!(out, "🎯 Sampling: {}", record.survey.describe())?;
+    for segment in &record.survey.data {
+        writeln!(out, "   ❌ bytes {}-{} (LBA {}): entropy {:.4} bits/byte, chi-square {:.1} - not wiped",
+            segment.offset, segment.offset + MIN_RANDOM_SEGMENT as u64 - 1,
+            segment.offset / record.geometry.logical, segment.entropy, segment.chi_square)?;
+    }
+    if record.survey.data_segments > record.survey.data.len() as u64 {
+        writeln!(out, "   ... and {} more segments with data", record.survey.data_segments - record.survey.data.len() as u64)?;
+    }
+
+    writeln!(out, "🧬 Residue scan: {}", record.residue.describe())?;
+    for hit in &record.residue.hits {
+        writeln!(out, "   ❌ {} at byte {} (LBA {})", hit.signature, hit.offset, hit.offset / record.geometry.logical)?;
+    }
+    if record.residue.total_hits > record.residue.hits.len() as u64 {
+        writeln!(out, "   ... and {} more hits", record.residue.total_hits - record.residue.hits.len() as u64)?;
+    }
+    if let Some(hpa) = &record.residue.hidden_area {
+        writeln!(out, "⚠️  Host protected area: {}", hpa)?;
+    }
+
+    writeln!(out, "🕒 Started: {} (Unix timestamp)", record.started)?;
+    writeln!(out, "🕒 Completed: {} (Unix timestamp)", record.finished)?;
+    writeln!(out, "{}", separator)?;
+    if findings.is_empty() {
+        writeln!(out, "✅ VERDICT: PASS - no trace of previous data found")?;
+    } else {
+        writeln!(out, "🛑 VERDICT: FAIL")?;
+        for finding in &findings {
+            writeln!(out, "   - {}", finding)?;
+        }
+    }
+    writeln!(out, "{}", separator)?;
+    Ok(())
+}
@@ -0,0 +1,689 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::checkpoint::write_atomically;
+use crate::disposition::Destination;
+use crate::pdf::{self, Page};
+use crate::range::ByteRange;
+use crate::report::{StepStatus, WipeRecord};
+use crate::signing::StationKey;
+
+pub const DEFAULT_CERTIFICATE_DIR: &str = "/var/lib/wipeshit/certificates";
+/// Version of the certificate format; bumped with every change to the schema.
+pub const SCHEMA_VERSION: &str = "1.3.0";
+pub const SCHEMA_ID: &str = "wipe-certificate-v1.schema.json";
+/// The published JSON Schema certificates of this version validate against.
+pub const SCHEMA: &str = include_str!("schema/wipe-certificate-v1.schema.json");
+
+/// Machine-readable record of one wipe. Built from the `WipeRecord` only,
+/// so it states exactly what the text report states.
+#[derive(Debug, Serialize)]
+pub struct Certificate {
+    #[serde(rename = "$schema")]
+    pub schema: &'static str,
+    pub schema_version: &'static str,
+    pub operation_id: String,
+    pub tool: Tool,
+    pub host: Host,
+    pub operator: String,
+    pub disposition: Disposition,
+    pub device: Device,
+    pub method: Method,
+    pub entropy: Option<String>,
+    pub steps: Vec<Step>,
+    pub result: Outcome,
+    pub verification: Verification,
+    pub provisioning: Option<Provisioning>,
+    pub started: u64,
+    pub finished: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Tool {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Host {
+    pub hostname: String,
+    pub kernel: String,
+}
+
+/// Operator-supplied NIST SP 800-88 sample certificate fields.
+#[derive(Debug, Serialize)]
+pub struct Disposition {
+    pub media_source: Option<String>,
+    /// Pre-sanitization confidentiality: "low", "moderate" or "high"
+    pub confidentiality: Option<&'static str>,
+    /// Post-sanitization destination
+    pub destination: Option<&'static str>,
+    pub validator: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Device {
+    pub path: String,
+    pub manufacturer: String,
+    pub media_type: String,
+    pub model: String,
+    pub serial: String,
+    pub size_bytes: u64,
+    pub logical_sector_size: u64,
+    pub physical_sector_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Method {
+    pub name: String,
+    pub nist_category: String,
+    pub technique: String,
+    pub range: Option<Range>,
+    pub encryption: Option<Encryption>,
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Range {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl From<&ByteRange> for Range {
+    fn from(range: &ByteRange) -> Self {
+        Range { start: range.start, end: range.end }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Encryption {
+    pub mode: String,
+    pub cipher: String,
+    pub key_size: u32,
+    pub sector_size: u32,
+    pub pbkdf: String,
+    pub pbkdf_cost: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Step {
+    pub name: String,
+    /// "completed", "failed" or "skipped"
+    pub status: &'static str,
+    /// Error of a failed step or reason for a skipped one
+    pub message: Option<String>,
+    pub started: u64,
+    pub duration_secs: f64,
+    pub details: Vec<Detail>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Detail {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Outcome {
+    /// "wiped", "incomplete" (unwritable sectors) or "failed"
+    pub status: &'static str,
+    pub failed_step: Option<String>,
+    pub bytes_written: u64,
+    pub defects: Vec<Range>,
+    pub resumes: Vec<Resume>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Resume {
+    pub offset: u64,
+    pub interrupted_at: u64,
+    pub resumed_at: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Verification {
+    pub method: String,
+    pub passed: Option<bool>,
+    pub sampled: Option<SampledVerification>,
+    pub full: Option<FullVerification>,
+    pub residue: Option<ResidueScan>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SampledVerification {
+    pub seed: u64,
+    pub samples: u64,
+    pub skipped_samples: u64,
+    pub failed_samples: u64,
+    pub bytes_read: u64,
+    pub coverage_percent: f64,
+    pub min_entropy: Option<f64>,
+    pub max_chi_square: Option<f64>,
+    pub failures: Vec<SampleFailure>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SampleFailure {
+    pub offset: u64,
+    pub expected: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FullVerification {
+    pub pattern: String,
+    pub bytes_read: u64,
+    pub bytes_skipped: u64,
+    pub duration_secs: f64,
+    pub mismatched_bytes: u64,
+    pub mismatches: Vec<Range>,
+    pub unreadable: Vec<Range>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResidueScan {
+    pub mode: &'static str,
+    pub seed: u64,
+    pub bytes_scanned: u64,
+    pub bytes_unreadable: u64,
+    pub total_hits: u64,
+    pub hits: Vec<ResidueHit>,
+    pub hidden_area: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResidueHit {
+    pub offset: u64,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Provisioning {
+    pub partition_table: Option<String>,
+    pub partition: String,
+    pub filesystem: String,
+    pub label: String,
+    pub filesystem_uuid: Option<String>,
+}
+
+fn read_proc(path: &str) -> String {
+    fs::read_to_string(path).map(|s| s.trim().to_string()).unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Operator recorded when none is given: the user who ran sudo, or the
+/// current user.
+pub fn default_operator() -> String {
+    ["SUDO_USER", "USER"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+pub fn build(record: &WipeRecord) -> Certificate {
+    let status = if record.succeeded() {
+        "wiped"
+    } else if record.finished.is_some() {
+        "incomplete"
+    } else {
+        "failed"
+    };
+
+    Certificate {
+        schema: SCHEMA_ID,
+        schema_version: SCHEMA_VERSION,
+        operation_id: record.operation_id.clone(),
+        tool: Tool { name: env!("CARGO_PKG_NAME"), version: env!("CARGO_PKG_VERSION") },
+        host: Host {
+            hostname: read_proc("/proc/sys/kernel/hostname"),
+            kernel: read_proc("/proc/sys/kernel/osrelease"),
+        },
+        operator: record.disposition.operator.clone(),
+        disposition: Disposition {
+            media_source: record.disposition.media_source.clone(),
+            confidentiality: record.disposition.confidentiality.map(|category| category.name()),
+            destination: record.disposition.destination.map(|destination| destination.name()),
+            validator: record.disposition.validator.clone(),
+        },
+        device: Device {
+            path: record.device.clone(),
+            manufacturer: record.media.manufacturer.clone(),
+            media_type: record.media.media_type.clone(),
+            model: record.identity.model.clone(),
+            serial: record.identity.serial.clone(),
+            size_bytes: record.identity.size_bytes,
+            logical_sector_size: record.geometry.logical,
+            physical_sector_size: record.geometry.physical,
+        },
+        method: Method {
+            name: record.method.clone(),
+            nist_category: record.nist_category().to_string(),
+            technique: record.technique().to_string(),
+            range: record.range.as_ref().map(Range::from),
+            encryption: record.encryption.as_ref().map(|params| Encryption {
+                mode: params.mode.clone(),
+                cipher: params.cipher.clone(),
+                key_size: params.key_size,
+                sector_size: params.sector_size,
+                pbkdf: params.pbkdf.clone(),
+                pbkdf_cost: params.pbkdf_cost.clone(),
+            }),
+            pattern: record.pattern.as_ref().map(|pattern| pattern.describe()),
+        },
+        entropy: record.entropy.as_ref().map(|status| status.describe()),
+        steps: record.steps.iter().map(|step| {
+            let (status, message) = match &step.status {
+                StepStatus::Completed => ("completed", None),
+                StepStatus::Failed(error) => ("failed", Some(error.clone())),
+                StepStatus::Skipped(reason) => ("skipped", Some(reason.clone())),
+            };
+            Step {
+                name: step.name.clone(),
+                status,
+                message,
+                started: step.started,
+                duration_secs: step.duration.as_secs_f64(),
+                details: step.details.iter()
+                    .map(|(name, value)| Detail { name: name.clone(), value: value.clone() })
+                    .collect(),
+            }
+        }).collect(),
+        result: Outcome {
+            status,
+            failed_step: record.failed_step().map(|step| step.name.clone()),
+            bytes_written: record.bytes_written,
+            defects: record.defects.iter().map(Range::from).collect(),
+            resumes: record.resumes.iter()
+                .map(|r| Resume { offset: r.offset, interrupted_at: r.interrupted_at, resumed_at: r.resumed_at })
+                .collect(),
+        },
+        verification: Verification {
+            method: record.verification_method(),
+            passed: record.verified,
+            sampled: record.verification.as_ref().map(|outcome| SampledVerification {
+                seed: outcome.seed,
+                samples: outcome.samples,
+                skipped_samples: outcome.skipped_samples,
+                failed_samples: outcome.failed_samples,
+                bytes_read: outcome.bytes_read,
+                coverage_percent: outcome.coverage(),
+                min_entropy: outcome.min_entropy,
+                max_chi_square: outcome.max_chi_square,
+                failures: outcome.failures.iter()
+                    .map(|f| SampleFailure { offset: f.offset, expected: f.expected.describe(), detail: f.detail.clone() })
+                    .collect(),
+            }),
+            full: record.full_verification.as_ref().map(|outcome| FullVerification {
+                pattern: outcome.pattern.describe(),
+                bytes_read: outcome.bytes_read,
+                bytes_skipped: outcome.bytes_skipped,
+                duration_secs: outcome.duration.as_secs_f64(),
+                mismatched_bytes: outcome.mismatched_bytes,
+                mismatches: outcome.mismatches.iter().map(Range::from).collect(),
+                unreadable: outcome.unreadable.iter().map(Range::from).collect(),
+            }),
+            residue: record.residue.as_ref().map(|outcome| ResidueScan {
+                mode: outcome.mode.name(),
+                seed: outcome.seed,
+                bytes_scanned: outcome.bytes_scanned,
+                bytes_unreadable: outcome.bytes_unreadable,
+                total_hits: outcome.total_hits,
+                hits: outcome.hits.iter()
+                    .map(|hit| ResidueHit { offset: hit.offset, signature: hit.signature.to_string() })
+                    .collect(),
+                hidden_area: outcome.hidden_area.clone(),
+            }),
+        },
+        provisioning: record.provisioned.as_ref().map(|provisioned| Provisioning {
+            partition_table: provisioned.table.map(|table| table.name().to_string()),
+            partition: provisioned.partition.clone(),
+            filesystem: provisioned.filesystem.name().to_string(),
+            label: provisioned.label.clone(),
+            filesystem_uuid: provisioned.fs_uuid.clone(),
+        }),
+        started: record.started,
+        finished: record.finished,
+    }
+}
+
+/// The certificate as pretty-printed JSON, signed with the station key.
+pub fn to_signed_json(certificate: &Certificate, station: &StationKey) -> io::Result<String> {
+    let json_error = |e: serde_json::Error| io::Error::other(e.to_string());
+    let mut document = serde_json::to_value(certificate).map_err(json_error)?;
+    station.sign(&mut document)?;
+    serde_json::to_string_pretty(&document).map_err(json_error)
+}
+
+fn format_time(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// Pages being filled top to bottom, starting a new page when one is full.
+struct Layout {
+    pages: Vec<Page>,
+    y: f32,
+}
+
+const MARGIN: f32 = 56.0;
+const LINE: f32 = 14.0;
+// Characters of 10 pt Helvetica that fit in the value column, counted at
+// the width of a digit so hex strings such as the key fingerprint fit too
+const VALUE_WRAP: usize = 58;
+const MAX_LISTED: usize = 10;
+
+impl Layout {
+    fn new() -> Self {
+        Layout { pages: vec![Page::default()], y: pdf::PAGE_HEIGHT - MARGIN }
+    }
+
+    fn page(&mut self) -> &mut Page {
+        self.pages.last_mut().unwrap()
+    }
+
+    fn advance(&mut self, height: f32) {
+        self.y -= height;
+        if self.y < MARGIN {
+            self.pages.push(Page::default());
+            self.y = pdf::PAGE_HEIGHT - MARGIN - height;
+        }
+    }
+
+    fn heading(&mut self, text: &str) {
+        self.advance(LINE * 1.8);
+        let y = self.y;
+        self.page().text(MARGIN, y, 12.0, true, text);
+        self.page().line(MARGIN, y - 4.0, pdf::PAGE_WIDTH - MARGIN, y - 4.0);
+        self.advance(4.0);
+    }
+
+    /// Label and value; long values wrap under the value column.
+    fn field(&mut self, label: &str, value: &str) {
+        for (i, line) in wrap(value, VALUE_WRAP).iter().enumerate() {
+            self.advance(LINE);
+            let y = self.y;
+            if i == 0 {
+                self.page().text(MARGIN, y, 10.0, true, label);
+            }
+            self.page().text(MARGIN + 150.0, y, 10.0, false, line);
+        }
+    }
+
+    fn signature_block(&mut self, role: &str, name: &str) {
+        self.advance(LINE * 1.5);
+        let y = self.y;
+        self.page().text(MARGIN, y, 10.0, true, role);
+        self.advance(LINE * 2.2);
+        let y = self.y;
+        let right = pdf::PAGE_WIDTH - MARGIN;
+        self.page().text(MARGIN, y + 3.0, 10.0, false, name);
+        self.page().line(MARGIN, y, MARGIN + 160.0, y);
+        self.page().line(MARGIN + 180.0, y, MARGIN + 360.0, y);
+        self.page().line(MARGIN + 380.0, y, right, y);
+        self.advance(LINE * 0.8);
+        let y = self.y;
+        self.page().text(MARGIN, y, 8.0, false, "Name");
+        self.page().text(MARGIN + 180.0, y, 8.0, false, "Signature");
+        self.page().text(MARGIN + 380.0, y, 8.0, false, "Date");
+    }
+}
+
+/// Split `value` into lines of at most `width` characters at spaces; a word
+/// longer than a line (a fingerprint, a long path) is broken inside.
+fn wrap(value: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in value.split_whitespace() {
+        let chars: Vec<char> = word.chars().collect();
+        for piece in chars.chunks(width) {
+            let used = current.chars().count();
+            if used > 0 && used + piece.len() + 1 > width {
+                lines.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.extend(piece);
+        }
+    }
+    lines.push(current);
+    lines
+}
+
+fn ranges(list: &[Range]) -> String {
+    let mut shown: Vec<String> = list.iter().take(MAX_LISTED).map(|r| format!("{}-{}", r.start, r.end - 1)).collect();
+    if list.len() > MAX_LISTED {
+        shown.push(format!("and {} more (see JSON certificate)", list.len() - MAX_LISTED));
+    }
+    shown.join(", ")
+}
+
+/// Printable certificate of sanitization with signature blocks for the
+/// technician and a witness, laid out from the same data as the JSON.
+pub fn render_pdf(certificate: &Certificate, key_fingerprint: &str) -> Vec<u8> {
+    let mut layout = Layout::new();
+    let y = layout.y;
+    layout.page().text(MARGIN, y, 18.0, true, "Certificate of Sanitization");
+    layout.advance(LINE);
+    layout.field("Operation ID", &certificate.operation_id);
+
+    let not_recorded = || "not recorded".to_string();
+    let disposition = &certificate.disposition;
+    layout.heading("Media");
+    let device = &certificate.device;
+    layout.field("Manufacturer", &device.manufacturer);
+    layout.field("Model", &device.model);
+    layout.field("Serial number", &device.serial);
+    layout.field("Media type", &device.media_type);
+    layout.field("Capacity", &format!("{} bytes ({:.1} GB)", device.size_bytes, device.size_bytes as f64 / 1e9));
+    layout.field("Sector size", &format!("{} B logical, {} B physical", device.logical_sector_size, device.physical_sector_size));
+    layout.field("Device path", &device.path);
+    layout.field("Media source", &disposition.media_source.clone().unwrap_or_else(not_recorded));
+    layout.field("Pre-sanitization", &disposition.confidentiality
+        .map(|category| format!("{} confidentiality", category))
+        .unwrap_or_else(not_recorded));
+
+    layout.heading("Sanitization");
+    let method = &certificate.method;
+    layout.field("Method type", &method.nist_category);
+    layout.field("Technique", &method.technique);
+    layout.field("Method", &method.name);
+    layout.field("Tool", &format!("{} {}", certificate.tool.name, certificate.tool.version));
+    if let Some(encryption) = &method.encryption {
+        layout.field("Encryption", &format!("{} {}, {}-bit key", encryption.mode, encryption.cipher, encryption.key_size));
+    }
+    if let Some(pattern) = &method.pattern {
+        layout.field("Pattern", pattern);
+    }
+    layout.field("Extent", &match &method.range {
+        Some(range) => format!("bytes {}-{} only", range.start, range.end - 1),
+        None => "entire device".to_string(),
+    });
+    let result = &certificate.result;
+    layout.field("Result", &match &result.failed_step {
+        Some(step) => format!("{} (step '{}' failed)", result.status, step),
+        None => result.status.to_string(),
+    });
+    layout.field("Bytes written", &result.bytes_written.to_string());
+    if !result.defects.is_empty() {
+        layout.field("Unwritable ranges", &ranges(&result.defects));
+    }
+    let destination = disposition.destination.and_then(|name| Destination::parse(name).ok()).map(|destination| destination.title());
+    layout.field("Destination", destination.unwrap_or("not recorded"));
+
+    layout.heading("Verification");
+    let verification = &certificate.verification;
+    layout.field("Method", &verification.method);
+    layout.field("Result", match verification.passed {
+        Some(true) => "passed",
+        Some(false) => "FAILED",
+        None => "not performed",
+    });
+    if let Some(sampled) = &verification.sampled {
+        layout.field("Sampled read-back", &format!("{} of {} samples failed, {:.3}% of the device read (seed {})",
+            sampled.failed_samples, sampled.samples, sampled.coverage_percent, sampled.seed));
+    }
+    if let Some(full) = &verification.full {
+        layout.field("Full read-back", &format!("{} bytes read in {:.1}s, {} bytes differ from the {}",
+            full.bytes_read, full.duration_secs, full.mismatched_bytes, full.pattern));
+        if !full.mismatches.is_empty() {
+            layout.field("Mismatching ranges", &ranges(&full.mismatches));
+        }
+    }
+    if let Some(residue) = &verification.residue {
+        layout.field("Residue scan", &format!("{} scan of {} bytes, {} file signatures found",
+            residue.mode, residue.bytes_scanned, residue.total_hits));
+    }
+
+    layout.heading("Record");
+    layout.field("Started", &format_time(certificate.started));
+    layout.field("Completed", &certificate.finished.map(format_time).unwrap_or_else(|| "not completed".to_string()));
+    layout.field("Host", &format!("{} (Linux {})", certificate.host.hostname, certificate.host.kernel));
+    layout.field("Signing key", &format!("Ed25519, {}", key_fingerprint));
+    layout.field("Authenticity", &format!("wipeshit verify-cert {}.json --expect-fingerprint {}",
+        certificate.operation_id, key_fingerprint));
+
+    layout.heading("Signatures");
+    layout.signature_block("Sanitized by", &certificate.operator);
+    layout.signature_block("Validated by", disposition.validator.as_deref().unwrap_or(""));
+
+    pdf::render(&layout.pages, &format!("Certificate of Sanitization {}", certificate.operation_id))
+}
+
+/// Write the JSON and PDF certificates of `record` to `<dir>/<operation_id>.json`
+/// and `.pdf`, replacing them atomically, and return their paths. The JSON is
+/// signed with the station key; the PDF names the key's fingerprint.
+pub fn write(record: &WipeRecord, dir: &str, station: &StationKey) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let certificate = build(record);
+    let json = Path::new(dir).join(format!("{}.json", record.operation_id));
+    write_atomically(&json, to_signed_json(&certificate, station)?)?;
+    let pdf = json.with_extension("pdf");
+    write_atomically(&pdf, render_pdf(&certificate, &station.fingerprint()))?;
+    Ok(vec![json, pdf])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use crate::disposition::Confidentiality;
+    use crate::geometry::SectorGeometry;
+    use crate::identity::DeviceIdentity;
+
+    /// Minimal check of `value` against the subset of JSON Schema the
+    /// certificate schema uses: $ref, oneOf, type, const, enum, required,
+    /// properties, additionalProperties and items.
+    fn check(value: &Value, schema: &Value, root: &Value, path: &str, errors: &mut Vec<String>) {
+        if let Some(pointer) = schema.get("$ref").and_then(Value::as_str) {
+            let target = root.pointer(pointer.trim_start_matches('#')).expect("dangling $ref");
+            return check(value, target, root, path, errors);
+        }
+        if let Some(alternatives) = schema.get("oneOf").and_then(Value::as_array) {
+            let matching = alternatives.iter().filter(|alternative| {
+                let mut inner = Vec::new();
+                check(value, alternative, root, path, &mut inner);
+                inner.is_empty()
+            }).count();
+            if matching != 1 {
+                errors.push(format!("{}: matches {} oneOf alternatives", path, matching));
+            }
+        }
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match types {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+                _ => vec![],
+            };
+            let actual = match value {
+                Value::Null => "null",
+                Value::Bool(_) => "boolean",
+                Value::Number(n) if n.is_u64() || n.is_i64() => "integer",
+                Value::Number(_) => "number",
+                Value::String(_) => "string",
+                Value::Array(_) => "array",
+                Value::Object(_) => "object",
+            };
+            if !(types.contains(&actual) || actual == "integer" && types.contains(&"number")) {
+                errors.push(format!("{}: {} is not {:?}", path, actual, types));
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if value != expected {
+                errors.push(format!("{}: {} is not {}", path, value, expected));
+            }
+        }
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                errors.push(format!("{}: {} not in enum", path, value));
+            }
+        }
+        if let Value::Object(object) = value {
+            for key in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+                if !object.contains_key(key.as_str().unwrap()) {
+                    errors.push(format!("{}: missing {}", path, key));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, child) in object {
+                match properties.and_then(|p| p.get(key)) {
+                    Some(child_schema) => check(child, child_schema, root, &format!("{}.{}", path, key), errors),
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        errors.push(format!("{}: unexpected {}", path, key))
+                    }
+                    None => {}
+                }
+            }
+        }
+        if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+            for (i, item) in items.iter().enumerate() {
+                check(item, item_schema, root, &format!("{}[{}]", path, i), errors);
+            }
+        }
+    }
+
+    #[test]
+    fn certificate_matches_published_schema() {
+        let identity = DeviceIdentity { model: "Test".into(), serial: "0".into(), size_bytes: 1 << 30 };
+        let mut record = WipeRecord::new("test", "/dev/null", &identity, "overwrite-zeros", SectorGeometry::default());
+        record.run_step("Prepare device", || Ok(())).unwrap();
+        record.detail("Target", "/dev/null");
+        record.skip_step("Verify", "not requested (--verify)");
+        record.range = Some(ByteRange { start: 0, end: 1 << 20 });
+        record.defects.push(ByteRange { start: 4096, end: 8192 });
+        record.finished = Some(record.started);
+        record.disposition.operator = "tester".into();
+        record.disposition.confidentiality = Some(Confidentiality::Moderate);
+        record.disposition.destination = Some(Destination::ExternalReuse);
+
+        let key_path = std::env::temp_dir().join(format!("wipeshit-test-{}.key", std::process::id()));
+        let station = StationKey::load_or_create(key_path.to_str().unwrap()).unwrap();
+        let _ = fs::remove_file(&key_path);
+
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let certificate: Value = serde_json::from_str(&to_signed_json(&build(&record), &station).unwrap()).unwrap();
+        assert_eq!(certificate["schema_version"], SCHEMA_VERSION);
+        assert_eq!(certificate["result"]["status"], "incomplete");
+        assert_eq!(crate::signing::verify(&certificate).unwrap().key_fingerprint, station.fingerprint());
+
+        let mut errors = Vec::new();
+        check(&certificate, &schema, &schema, "$", &mut errors);
+        assert!(errors.is_empty(), "{:#?}", errors);
+    }
+
+    #[test]
+    fn long_values_wrap_inside_the_value_column() {
+        let hex = "ab12".repeat(16);
+        let fingerprint = format!("Ed25519, SHA256:{}", hex);
+        let lines = wrap(&fingerprint, VALUE_WRAP);
+        assert_eq!(lines, ["Ed25519,".to_string(), format!("SHA256:{}", &hex[..51]), hex[51..].to_string()]);
+        assert!(lines.iter().all(|line| line.chars().count() <= VALUE_WRAP));
+        assert_eq!(lines.concat(), fingerprint.replace(' ', ""));
+
+        assert_eq!(wrap("short value", VALUE_WRAP), ["short value"]);
+        assert_eq!(wrap("", VALUE_WRAP), [""]);
+        let prose = "word ".repeat(30);
+        assert!(wrap(&prose, VALUE_WRAP).iter().all(|line| line.len() <= VALUE_WRAP && !line.ends_with(' ')));
+    }
+}
@@ -0,0 +1,321 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+use crate::identity::DeviceIdentity;
+use crate::range::ByteRange;
+
+pub const DEFAULT_CHECKPOINT_DIR: &str = "/var/lib/wipeshit/checkpoints";
+const TMPFS_MAGIC: i64 = 0x0102_1994;
+
+/// Progress of an interrupted wipe, persisted outside the target device so
+/// the job can pick up where it stopped after a crash or power loss.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub operation_id: String,
+    pub device: String,
+    pub identity: DeviceIdentity,
+    pub method: String,
+    pub pass: u32,
+    pub passes: u32,
+    pub range: Option<ByteRange>,
+    /// Seed of a random overwrite stream, so a resumed pass writes the same
+    /// stream and the read-back can regenerate it
+    pub pattern_seed: Option<[u8; 32]>,
+    pub offset: u64,
+    pub started: u64,
+    pub updated: u64,
+    pub resumes: Vec<ResumeEvent>,
+    pub defects: Vec<ByteRange>,
+}
+
+/// One interruption: where the job had durably got to and when it was resumed.
+#[derive(Debug, Clone)]
+pub struct ResumeEvent {
+    pub offset: u64,
+    pub interrupted_at: u64,
+    pub resumed_at: u64,
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// sysfs path of the block device holding `dev_t`, e.g.
+/// /sys/devices/.../block/sdb/sdb1. A partition's path lies below its disk's.
+fn sysfs_block_path(dev: u64) -> Option<PathBuf> {
+    let (major, minor) = (libc::major(dev), libc::minor(dev));
+    fs::canonicalize(format!("/sys/dev/block/{}:{}", major, minor)).ok()
+}
+
+/// Refuse to keep checkpoints on the disk that is about to be wiped.
+fn ensure_outside_target(dir: &Path, device: &str) -> io::Result<()> {
+    let dir_dev = fs::metadata(dir)?.dev();
+    let target_dev = fs::metadata(device)?.rdev();
+
+    if let (Some(dir_path), Some(target_path)) = (sysfs_block_path(dir_dev), sysfs_block_path(target_dev)) {
+        let target_disk = if target_path.join("partition").exists() {
+            target_path.parent().map(Path::to_path_buf).unwrap_or(target_path)
+        } else {
+            target_path
+        };
+        if dir_path.starts_with(&target_disk) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Checkpoint directory {} is on {} - choose storage outside the target", dir.display(), device)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn is_tmpfs(dir: &Path) -> bool {
+    let path = match std::ffi::CString::new(dir.as_os_str().as_encoded_bytes()) {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    let mut stats: libc::statfs = unsafe { std::mem::zeroed() };
+    unsafe { libc::statfs(path.as_ptr(), &mut stats) == 0 && stats.f_type as i64 == TMPFS_MAGIC }
+}
+
+/// Replace a state file so that a crash leaves either the old or the new
+/// contents, never a torn file (write to a temp file, fsync, rename).
+pub fn write_atomically(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    // The temp name is unique so concurrent writers never rename each
+    // other's half-written file
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(format!(".{}.tmp", Uuid::new_v4().simple()));
+    let tmp_path = PathBuf::from(tmp_name);
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(content.as_ref())?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)?;
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Checkpoint files in a directory, one per drive. A store without a
+/// directory keeps nothing: it is used for drives that cannot be identified
+/// again and therefore never resume.
+pub struct CheckpointStore {
+    dir: Option<PathBuf>,
+}
+
+impl CheckpointStore {
+    pub fn open(dir: &str, device: &str) -> io::Result<Self> {
+        let dir = PathBuf::from(dir);
+        fs::create_dir_all(&dir)?;
+        ensure_outside_target(&dir, device)?;
+        if is_tmpfs(&dir) {
+            println!("⚠️  Checkpoint directory {} is in RAM - checkpoints will not survive a reboot", dir.display());
+            println!("   Use --checkpoint-dir to point at persistent storage");
+        }
+        Ok(CheckpointStore { dir: Some(dir) })
+    }
+
+    pub fn disabled() -> Self {
+        CheckpointStore { dir: None }
+    }
+
+    fn path_for(&self, identity: &DeviceIdentity) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.ckpt", identity.key())))
+    }
+
+    pub fn load(&self, identity: &DeviceIdentity) -> io::Result<Option<Checkpoint>> {
+        let path = match self.path_for(identity) {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        let content = fs::read_to_string(&path)?;
+        parse_checkpoint(&content).map(Some).ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Corrupt checkpoint file {}", path.display())
+        ))
+    }
+
+    pub fn save(&self, checkpoint: &Checkpoint) -> io::Result<()> {
+        match self.path_for(&checkpoint.identity) {
+            Some(path) => write_atomically(&path, format_checkpoint(checkpoint)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn remove(&self, identity: &DeviceIdentity) -> io::Result<()> {
+        match self.path_for(identity).map(fs::remove_file) {
+            Some(Err(e)) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn format_checkpoint(checkpoint: &Checkpoint) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("operation_id={}\n", checkpoint.operation_id));
+    out.push_str(&format!("device={}\n", checkpoint.device));
+    out.push_str(&format!("model={}\n", checkpoint.identity.model));
+    out.push_str(&format!("serial={}\n", checkpoint.identity.serial));
+    out.push_str(&format!("size_bytes={}\n", checkpoint.identity.size_bytes));
+    out.push_str(&format!("method={}\n", checkpoint.method));
+    out.push_str(&format!("pass={}\n", checkpoint.pass));
+    out.push_str(&format!("passes={}\n", checkpoint.passes));
+    if let Some(range) = &checkpoint.range {
+        out.push_str(&format!("range={}:{}\n", range.start, range.end));
+    }
+    if let Some(seed) = &checkpoint.pattern_seed {
+        out.push_str(&format!("pattern_seed={}\n", hex::encode(seed)));
+    }
+    out.push_str(&format!("offset={}\n", checkpoint.offset));
+    out.push_str(&format!("started={}\n", checkpoint.started));
+    out.push_str(&format!("updated={}\n", checkpoint.updated));
+    for resume in &checkpoint.resumes {
+        out.push_str(&format!("resume={},{},{}\n", resume.offset, resume.interrupted_at, resume.resumed_at));
+    }
+    for defect in &checkpoint.defects {
+        out.push_str(&format!("defect={}:{}\n", defect.start, defect.end));
+    }
+    out
+}
+
+fn parse_checkpoint(content: &str) -> Option<Checkpoint> {
+    let mut checkpoint = Checkpoint {
+        operation_id: String::new(),
+        device: String::new(),
+        identity: DeviceIdentity { model: String::new(), serial: String::new(), size_bytes: 0 },
+        method: String::new(),
+        pass: 1,
+        passes: 1,
+        range: None,
+        pattern_seed: None,
+        offset: 0,
+        started: 0,
+        updated: 0,
+        resumes: Vec::new(),
+        defects: Vec::new(),
+    };
+
+    for line in content.lines() {
+        let (key, value) = line.split_once('=')?;
+        match key {
+            "operation_id" => checkpoint.operation_id = value.to_string(),
+            "device" => checkpoint.device = value.to_string(),
+            "model" => checkpoint.identity.model = value.to_string(),
+            "serial" => checkpoint.identity.serial = value.to_string(),
+            "size_bytes" => checkpoint.identity.size_bytes = value.parse().ok()?,
+            "method" => checkpoint.method = value.to_string(),
+            "pass" => checkpoint.pass = value.parse().ok()?,
+            "passes" => checkpoint.passes = value.parse().ok()?,
+            "range" => {
+                let (start, end) = value.split_once(':')?;
+                checkpoint.range = Some(ByteRange { start: start.parse().ok()?, end: end.parse().ok()? });
+            }
+            "pattern_seed" => checkpoint.pattern_seed = Some(hex::decode(value).ok()?.try_into().ok()?),
+            "offset" => checkpoint.offset = value.parse().ok()?,
+            "started" => checkpoint.started = value.parse().ok()?,
+            "updated" => checkpoint.updated = value.parse().ok()?,
+            "defect" => {
+                let (start, end) = value.split_once(':')?;
+                checkpoint.defects.push(ByteRange { start: start.parse().ok()?, end: end.parse().ok()? });
+            }
+            "resume" => {
+                let mut fields = value.split(',').map(|f| f.parse::<u64>());
+                checkpoint.resumes.push(ResumeEvent {
+                    offset: fields.next()?.ok()?,
+                    interrupted_at: fields.next()?.ok()?,
+                    resumed_at: fields.next()?.ok()?,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if checkpoint.operation_id.is_empty() {
+        return None;
+    }
+    Some(checkpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoint_round_trips() {
+        let checkpoint = Checkpoint {
+            operation_id: "0b6f7c1e-6d1a-4f0e-9a51-2c3d4e5f6a7b".to_string(),
+            device: "/dev/sdb".to_string(),
+            identity: DeviceIdentity { model: "WDC WD10EZEX-08W".to_string(), serial: "WD-WCC3F1234567".to_string(), size_bytes: 1_000_204_886_016 },
+            method: "overwrite-random".to_string(),
+            pass: 1,
+            passes: 1,
+            range: Some(ByteRange { start: 1 << 20, end: 1 << 30 }),
+            pattern_seed: Some([0xa5; 32]),
+            offset: 536_870_912,
+            started: 1_700_000_000,
+            updated: 1_700_000_600,
+            resumes: vec![
+                ResumeEvent { offset: 134_217_728, interrupted_at: 1_700_000_100, resumed_at: 1_700_000_200 },
+                ResumeEvent { offset: 268_435_456, interrupted_at: 1_700_000_300, resumed_at: 1_700_000_400 },
+            ],
+            defects: vec![ByteRange { start: 4096, end: 8192 }, ByteRange { start: 1 << 25, end: (1 << 25) + 512 }],
+        };
+
+        let text = format_checkpoint(&checkpoint);
+        let parsed = parse_checkpoint(&text).expect("checkpoint does not parse");
+        assert_eq!(parsed.identity, checkpoint.identity);
+        assert_eq!(parsed.range, checkpoint.range);
+        assert_eq!(parsed.pattern_seed, checkpoint.pattern_seed);
+        assert_eq!(parsed.offset, checkpoint.offset);
+        assert_eq!(parsed.resumes.len(), 2);
+        assert_eq!(parsed.resumes[1].resumed_at, 1_700_000_400);
+        assert_eq!(parsed.defects, checkpoint.defects);
+        assert_eq!(format_checkpoint(&parsed), text);
+    }
+
+    #[test]
+    fn corrupt_checkpoint_is_rejected() {
+        assert!(parse_checkpoint("offset=12\n").is_none());
+        assert!(parse_checkpoint("operation_id=x\noffset=twelve\n").is_none());
+        assert!(parse_checkpoint("operation_id=x\nresume=1,2\n").is_none());
+        assert!(parse_checkpoint("operation_id=x\npattern_seed=abcd\n").is_none());
+    }
+
+    #[test]
+    fn concurrent_writers_do_not_collide() {
+        let dir = std::env::temp_dir().join(format!("wipeshit-ckpt-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("shared.ckpt");
+        let writers: Vec<_> = (0..4).map(|i| {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                for _ in 0..50 {
+                    write_atomically(&path, format!("writer={}\n", i)).unwrap();
+                }
+            })
+        }).collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert!(fs::read_to_string(&path).unwrap().starts_with("writer="));
+        // No temp files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn disabled_store_keeps_nothing() {
+        let store = CheckpointStore::disabled();
+        let identity = DeviceIdentity { model: "Flash Disk".to_string(), serial: "Unknown".to_string(), size_bytes: 1 << 30 };
+        assert!(store.load(&identity).unwrap().is_none());
+        assert!(store.remove(&identity).is_ok());
+    }
+}
@@ -0,0 +1,393 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+use crate::checkpoint::is_tmpfs;
+use crate::range;
+use crate::write_engine::AlignedBuffer;
+
+// tmpfs locations tried for detached headers, in order
+const HEADER_DIRS: &[&str] = &["/run/wipeshit/headers", "/dev/shm/wipeshit"];
+
+/// Key derivation for the throwaway passphrase. The passphrase is random and
+/// never stored, so a slow PBKDF buys nothing; `Minimal` keeps formatting fast
+/// on weak hardware.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pbkdf {
+    Argon2id,
+    Argon2i,
+    Pbkdf2,
+    Minimal,
+}
+
+impl Pbkdf {
+    pub fn parse(value: &str) -> io::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "argon2id" => Ok(Pbkdf::Argon2id),
+            "argon2i" => Ok(Pbkdf::Argon2i),
+            "pbkdf2" => Ok(Pbkdf::Pbkdf2),
+            "minimal" => Ok(Pbkdf::Minimal),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown PBKDF '{}' (expected argon2id, argon2i, pbkdf2 or minimal)", other)
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pbkdf::Argon2id => "argon2id",
+            Pbkdf::Argon2i => "argon2i",
+            Pbkdf::Pbkdf2 => "pbkdf2",
+            Pbkdf::Minimal => "minimal",
+        }
+    }
+}
+
+/// Parameters handed to `cryptsetup luksFormat`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LuksParams {
+    pub cipher: String,
+    pub key_size: u32,
+    pub hash: String,
+    pub pbkdf: Pbkdf,
+}
+
+impl Default for LuksParams {
+    fn default() -> Self {
+        LuksParams {
+            cipher: "aes-xts-plain64".to_string(),
+            key_size: 512,
+            hash: "sha256".to_string(),
+            pbkdf: Pbkdf::Argon2id,
+        }
+    }
+}
+
+impl LuksParams {
+    /// Build from the command line values; `None` keeps the default.
+    pub fn new(cipher: Option<&str>, key_size: Option<&str>, hash: Option<&str>, pbkdf: Option<&str>) -> io::Result<Self> {
+        let mut params = LuksParams::default();
+        if let Some(cipher) = cipher {
+            params.cipher = cipher.to_string();
+            params.key_size = default_key_size(cipher);
+        }
+        if let Some(value) = key_size {
+            params.key_size = match value.parse::<u32>() {
+                Ok(bits) if bits > 0 && bits % 8 == 0 => bits,
+                _ => return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid key size '{}': expected a number of bits divisible by 8", value)
+                )),
+            };
+        }
+        if let Some(hash) = hash {
+            params.hash = hash.to_string();
+        }
+        if let Some(pbkdf) = pbkdf {
+            params.pbkdf = Pbkdf::parse(pbkdf)?;
+        }
+
+        // XTS splits the key in two halves; anything below 2x128 bits is weak
+        if params.cipher.contains("-xts-") && params.key_size < 256 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} needs a key size of at least 256 bits", params.cipher)
+            ));
+        }
+        Ok(params)
+    }
+
+    /// Arguments for `cryptsetup luksFormat`, `iter_time` in milliseconds.
+    pub fn format_args(&self, iter_time: &str) -> Vec<String> {
+        let mut args: Vec<String> = vec![
+            "--cipher".into(), self.cipher.clone(),
+            "--key-size".into(), self.key_size.to_string(),
+            "--hash".into(), self.hash.clone(),
+        ];
+        match self.pbkdf {
+            // Lowest cost cryptsetup accepts for PBKDF2
+            Pbkdf::Minimal => args.extend(["--pbkdf", "pbkdf2", "--pbkdf-force-iterations", "1000"].map(String::from)),
+            pbkdf => args.extend(["--pbkdf".to_string(), pbkdf.name().to_string(), "--iter-time".to_string(), iter_time.to_string()]),
+        }
+        args
+    }
+}
+
+fn default_key_size(cipher: &str) -> u32 {
+    if cipher.contains("adiantum") {
+        256
+    } else if cipher.contains("-xts-") {
+        512
+    } else {
+        256
+    }
+}
+
+/// One line of `cryptsetup benchmark` for a cipher.
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    pub algorithm: String,
+    pub key_size: u32,
+    pub encryption_mibs: f64,
+}
+
+// Ciphers `--cipher auto` chooses from, as named by `cryptsetup benchmark`,
+// with the key size and dm-crypt spec to use. Only AES-XTS (NIST SP 800-38E)
+// is here, so an automatic choice never undermines a NIST SP 800-88 claim;
+// Serpent, Twofish or Adiantum have to be asked for with an explicit --cipher
+const AUTO_CIPHERS: &[(&str, u32, &str)] = &[
+    ("aes-xts", 512, "aes-xts-plain64"),
+    ("aes-xts", 256, "aes-xts-plain64"),
+];
+
+pub fn benchmark() -> io::Result<Vec<BenchmarkResult>> {
+    let mut results = Vec::new();
+    for (algorithm, key_size, _) in AUTO_CIPHERS {
+        let output = ProcessCommand::new("cryptsetup")
+            .args(["benchmark", "--cipher", algorithm, "--key-size", &key_size.to_string()])
+            .output()?;
+        if !output.status.success() {
+            // Cipher not available in this kernel
+            continue;
+        }
+        if let Some(result) = parse_benchmark(&String::from_utf8_lossy(&output.stdout), algorithm, *key_size) {
+            results.push(result);
+        }
+    }
+    Ok(results)
+}
+
+fn parse_benchmark(output: &str, algorithm: &str, key_size: u32) -> Option<BenchmarkResult> {
+    // "        aes-xts        512b      2195.3 MiB/s      2201.7 MiB/s"
+    let line = output
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .find(|line| line.split_whitespace().next() == Some(algorithm))?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let unit = fields.iter().position(|f| *f == "MiB/s")?;
+    let encryption_mibs = fields.get(unit.checked_sub(1)?)?.parse().ok()?;
+    Some(BenchmarkResult { algorithm: algorithm.to_string(), key_size, encryption_mibs })
+}
+
+/// Benchmark the secure ciphers and return `base` with the fastest one.
+pub fn choose_fastest(base: &LuksParams) -> io::Result<LuksParams> {
+    println!("⏱️  Benchmarking ciphers...");
+    let results = benchmark()?;
+    for result in &results {
+        println!("   {:<24} {:>4} bits  {:>8.1} MiB/s", result.algorithm, result.key_size, result.encryption_mibs);
+    }
+
+    let fastest = results
+        .iter()
+        .max_by(|a, b| a.encryption_mibs.total_cmp(&b.encryption_mibs))
+        .ok_or_else(|| io::Error::other(
+            "cryptsetup benchmark found no usable AES-XTS - choose a cipher with an explicit --cipher"
+        ))?;
+    let (_, key_size, spec) = AUTO_CIPHERS
+        .iter()
+        .find(|(name, key_size, _)| *name == fastest.algorithm && *key_size == fastest.key_size)
+        .unwrap();
+    println!("✅ Fastest approved cipher: {} with a {}-bit key ({:.1} MiB/s)", spec, key_size, fastest.encryption_mibs);
+
+    Ok(LuksParams { cipher: spec.to_string(), key_size: *key_size, ..base.clone() })
+}
+
+/// Encryption parameters as actually in effect, read back from the LUKS2
+/// header or the kernel's mapping table so the report shows what was used
+/// rather than what was asked for.
+#[derive(Debug, Clone, Default)]
+pub struct CryptParams {
+    /// "LUKS2" or "plain dm-crypt"
+    pub mode: String,
+    pub cipher: String,
+    pub key_size: u32,
+    pub sector_size: u32,
+    pub pbkdf: String,
+    pub pbkdf_cost: String,
+}
+
+impl CryptParams {
+    pub fn describe(&self) -> String {
+        let kdf = if self.pbkdf_cost.is_empty() {
+            self.pbkdf.clone()
+        } else {
+            format!("{} ({})", self.pbkdf, self.pbkdf_cost)
+        };
+        format!("{} {}, {}-bit key, {}-byte sectors, key derivation: {}",
+            self.mode, self.cipher, self.key_size, self.sector_size, kdf)
+    }
+}
+
+pub fn read_header(device: &str) -> io::Result<CryptParams> {
+    let output = ProcessCommand::new("cryptsetup")
+        .args(["luksDump", device])
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            format!("Failed to read LUKS header: {}", String::from_utf8_lossy(&output.stderr).trim())
+        ));
+    }
+
+    let mut header = CryptParams { mode: "LUKS2".to_string(), ..CryptParams::default() };
+    let mut cost = Vec::new();
+    let mut in_pbkdf = false;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let (key, value) = match line.trim().split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+        let number = || value.split_whitespace().next().and_then(|n| n.parse().ok()).unwrap_or(0);
+        // Only the first segment and keyslot matter; there is one of each
+        match key {
+            "cipher" if header.cipher.is_empty() => header.cipher = value.to_string(),
+            "sector" if header.sector_size == 0 => header.sector_size = number(),
+            "Key" if header.key_size == 0 => header.key_size = number(),
+            "PBKDF" if header.pbkdf.is_empty() => {
+                header.pbkdf = value.to_string();
+                in_pbkdf = true;
+            }
+            "Time cost" | "Memory" | "Threads" | "Iterations" if in_pbkdf => {
+                cost.push(format!("{} {}", key.to_lowercase(), value));
+            }
+            // The salt follows the cost parameters of a keyslot
+            "Salt" => in_pbkdf = false,
+            _ => {}
+        }
+    }
+    header.pbkdf_cost = cost.join(", ");
+    Ok(header)
+}
+
+const LUKS_MAGIC: &[u8] = b"LUKS\xba\xbe";
+const LUKS2_SECONDARY_MAGIC: &[u8] = b"SKUL\xba\xbe";
+// The LUKS2 secondary header follows the primary's metadata area, whose size
+// is one of these; LUKS1 only has a header at offset 0
+pub const LUKS2_SECONDARY_OFFSETS: &[u64] = &[
+    16 << 10, 32 << 10, 64 << 10, 128 << 10, 256 << 10, 512 << 10, 1 << 20, 2 << 20, 4 << 20,
+];
+
+/// Look for LUKS headers that survived destruction: the magic of a LUKS1 or
+/// LUKS2 primary header at offset 0, of a LUKS2 secondary header at every
+/// possible offset, and whatever `cryptsetup isLuks` recognises. Reads bypass
+/// the page cache so only what is on the media counts. Returns a description
+/// of each header found.
+pub fn find_luks_headers(device: &str) -> io::Result<Vec<String>> {
+    let file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(device)?;
+    let size = range::device_size(device)?;
+    let mut buffer = AlignedBuffer::zeroed(4096, 4096);
+    let mut found = Vec::new();
+
+    let locations = std::iter::once((0, LUKS_MAGIC, "primary"))
+        .chain(LUKS2_SECONDARY_OFFSETS.iter().map(|&offset| (offset, LUKS2_SECONDARY_MAGIC, "secondary")));
+    for (offset, magic, kind) in locations {
+        if offset + 4096 > size {
+            continue;
+        }
+        let data = buffer.as_mut_slice();
+        file.read_exact_at(data, offset)?;
+        if &data[..magic.len()] == magic {
+            let version = u16::from_be_bytes([data[6], data[7]]);
+            found.push(format!("LUKS{} {} header at offset {}", version, kind, offset));
+        }
+    }
+
+    let output = ProcessCommand::new("cryptsetup")
+        .args(["isLuks", device])
+        .output()?;
+    if output.status.success() {
+        found.push("cryptsetup isLuks still recognises a LUKS header".to_string());
+    }
+
+    Ok(found)
+}
+
+/// Read the parameters of an open plain dm-crypt mapping from its table:
+/// `0 <len> crypt <cipher> <key> <iv_offset> <device> <offset> [<n> <opts>...]`.
+/// The key itself is never printed by dmsetup without --showkeys; only its
+/// length is taken.
+pub fn read_mapping(mapper_name: &str) -> io::Result<CryptParams> {
+    let output = ProcessCommand::new("dmsetup")
+        .args(["table", mapper_name])
+        .output()?;
+    let table = String::from_utf8_lossy(&output.stdout).to_string();
+    let fields: Vec<&str> = table.split_whitespace().collect();
+    if !output.status.success() || fields.len() < 8 || fields[2] != "crypt" {
+        return Err(io::Error::other(
+            format!("Unexpected dm-crypt table for {}: {}", mapper_name, table.trim())
+        ));
+    }
+
+    // Key is hex, or ":<bytes>:<type>:<description>" when held in the keyring
+    let key_bytes = match fields[4].strip_prefix(':') {
+        Some(keyring) => keyring.split(':').next().and_then(|n| n.parse::<u32>().ok()).unwrap_or(0),
+        None => fields[4].len() as u32 / 2,
+    };
+    let sector_size = fields[8..]
+        .iter()
+        .find_map(|opt| opt.strip_prefix("sector_size:"))
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(512);
+
+    Ok(CryptParams {
+        mode: "plain dm-crypt".to_string(),
+        cipher: fields[3].to_string(),
+        key_size: key_bytes * 8,
+        sector_size,
+        pbkdf: "none - random key from the kernel CSPRNG".to_string(),
+        pbkdf_cost: String::new(),
+    })
+}
+
+/// LUKS2 header kept in a tmpfs file instead of on the target, so the device
+/// never holds a key slot and nothing has to be overwritten afterwards. The
+/// file is overwritten and removed on drop.
+pub struct DetachedHeader {
+    path: PathBuf,
+}
+
+impl DetachedHeader {
+    pub fn create(name: &str) -> io::Result<Self> {
+        let dir = HEADER_DIRS
+            .iter()
+            .map(Path::new)
+            .find(|dir| fs::create_dir_all(dir).is_ok() && is_tmpfs(dir))
+            .ok_or_else(|| io::Error::other(
+                format!("No tmpfs available for the detached header (tried {})", HEADER_DIRS.join(", "))
+            ))?;
+
+        if fs::read_to_string("/proc/swaps").is_ok_and(|swaps| swaps.lines().count() > 1) {
+            println!("⚠️  Swap is active - tmpfs pages holding the header could be swapped out");
+        }
+
+        let path = dir.join(format!("{}.hdr", name));
+        OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path)?;
+        Ok(DetachedHeader { path })
+    }
+
+    pub fn path(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+
+    /// Overwrite the header file with zeros and delete it.
+    pub fn discard(&self) -> io::Result<()> {
+        let mut file = OpenOptions::new().write(true).open(&self.path)?;
+        let len = file.metadata()?.len() as usize;
+        file.write_all(&vec![0u8; len])?;
+        file.sync_all()?;
+        drop(file);
+        fs::remove_file(&self.path)
+    }
+}
+
+impl Drop for DetachedHeader {
+    fn drop(&mut self) {
+        if self.path.exists() {
+            let _ = self.discard();
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::io;
+
+/// FIPS 199 confidentiality impact of the data the media held before it was
+/// sanitized, as asked for by the NIST SP 800-88 sample certificate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Confidentiality {
+    Low,
+    Moderate,
+    High,
+}
+
+impl Confidentiality {
+    pub fn parse(value: &str) -> io::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "low" => Ok(Confidentiality::Low),
+            "moderate" | "medium" => Ok(Confidentiality::Moderate),
+            "high" => Ok(Confidentiality::High),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown confidentiality category '{}' (expected low, moderate or high)", other)
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Confidentiality::Low => "low",
+            Confidentiality::Moderate => "moderate",
+            Confidentiality::High => "high",
+        }
+    }
+}
+
+/// Where the media goes after sanitization.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Destination {
+    InternalReuse,
+    ExternalReuse,
+    Recycling,
+    Manufacturer,
+    Other,
+}
+
+impl Destination {
+    pub fn parse(value: &str) -> io::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "internal-reuse" | "internal" => Ok(Destination::InternalReuse),
+            "external-reuse" | "external" | "resale" => Ok(Destination::ExternalReuse),
+            "recycling" | "recycle" => Ok(Destination::Recycling),
+            "manufacturer" | "rma" => Ok(Destination::Manufacturer),
+            "other" => Ok(Destination::Other),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown destination '{}' (expected internal-reuse, external-reuse, recycling, manufacturer or other)", other)
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Destination::InternalReuse => "internal-reuse",
+            Destination::ExternalReuse => "external-reuse",
+            Destination::Recycling => "recycling",
+            Destination::Manufacturer => "manufacturer",
+            Destination::Other => "other",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Destination::InternalReuse => "Internal reuse",
+            Destination::ExternalReuse => "External reuse",
+            Destination::Recycling => "Recycling facility",
+            Destination::Manufacturer => "Manufacturer",
+            Destination::Other => "Other",
+        }
+    }
+}
+
+/// What the operator states about the media and who answers for the wipe.
+/// None of it can be read from the drive, so unset fields stay unset in the
+/// report and certificate rather than being guessed.
+#[derive(Debug, Clone, Default)]
+pub struct Disposition {
+    pub operator: String,
+    /// Person who checks the wipe and signs the certificate as validator
+    pub validator: Option<String>,
+    /// Owner, asset tag or other origin of the media
+    pub media_source: Option<String>,
+    pub confidentiality: Option<Confidentiality>,
+    pub destination: Option<Destination>,
+}
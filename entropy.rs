@@ -0,0 +1,247 @@
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use zeroize::Zeroize;
+
+// Health test parameters from NIST SP 800-90B section 4.4, for byte samples
+// with an assessed entropy of H = 8 bits and a false positive rate of 2^-40
+// (low enough that a station never aborts a wipe on a healthy source):
+// RCT cutoff C = 1 + ceil(40 / H); APT cutoff is the binomial critical value
+// for W = 512, p = 2^-8
+const RCT_CUTOFF: u32 = 6;
+const APT_WINDOW: u32 = 512;
+const APT_CUTOFF: u32 = 20;
+// Start-up testing runs over at least 1024 samples before any output is used
+const STARTUP_SAMPLES: usize = 4096;
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Outcome of the entropy checks, recorded in the report.
+#[derive(Debug, Clone)]
+pub struct EntropyStatus {
+    pub ready_after: Duration,
+    pub startup_samples: usize,
+    pub samples_tested: u64,
+    pub longest_run: u32,
+    pub max_window_count: u32,
+}
+
+impl EntropyStatus {
+    pub fn describe(&self) -> String {
+        format!("kernel CSPRNG ready after {:.1}s; SP 800-90B health tests passed on {} bytes \
+                 (start-up {}, longest repetition {} < {}, max APT count {} < {} per {})",
+            self.ready_after.as_secs_f64(),
+            self.samples_tested,
+            self.startup_samples,
+            self.longest_run, RCT_CUTOFF,
+            self.max_window_count, APT_CUTOFF, APT_WINDOW)
+    }
+}
+
+/// The kernel CSPRNG behind continuous repetition count and adaptive
+/// proportion tests. Every byte handed out has passed both tests; a failure
+/// is returned as an error and the source refuses to produce more.
+pub struct EntropySource {
+    status: EntropyStatus,
+    failed: bool,
+    // Repetition count test state
+    last: Option<u8>,
+    run: u32,
+    // Adaptive proportion test state
+    window_value: u8,
+    window_seen: u32,
+    window_count: u32,
+}
+
+fn health_failure(message: String) -> io::Error {
+    io::Error::other(format!("Entropy health test failed: {}", message))
+}
+
+/// Wait until the kernel reports its CSPRNG as initialised, i.e. a
+/// non-blocking getrandom() no longer returns EAGAIN.
+fn wait_until_ready() -> io::Result<Duration> {
+    let started = Instant::now();
+    let mut warned = false;
+    loop {
+        let mut probe = [0u8; 1];
+        let result = unsafe {
+            libc::getrandom(probe.as_mut_ptr() as *mut libc::c_void, probe.len(), libc::GRND_NONBLOCK)
+        };
+        if result == 1 {
+            return Ok(started.elapsed());
+        }
+        let error = io::Error::last_os_error();
+        if error.raw_os_error() != Some(libc::EAGAIN) && error.raw_os_error() != Some(libc::EINTR) {
+            return Err(error);
+        }
+        if started.elapsed() >= READY_TIMEOUT {
+            return Err(io::Error::other(
+                format!("Kernel CSPRNG not initialised after {}s - refusing to generate keys", READY_TIMEOUT.as_secs())
+            ));
+        }
+        if !warned {
+            println!("⏳ Kernel random pool not initialised yet, waiting for entropy...");
+            warned = true;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+impl EntropySource {
+    /// Readiness check followed by the start-up health test.
+    pub fn start() -> io::Result<Self> {
+        let mut source = Self::new(wait_until_ready()?);
+
+        // Start-up samples are tested and discarded
+        let mut startup = vec![0u8; STARTUP_SAMPLES];
+        source.fill(&mut startup)?;
+        Ok(source)
+    }
+
+    fn new(ready_after: Duration) -> Self {
+        EntropySource {
+            status: EntropyStatus {
+                ready_after,
+                startup_samples: STARTUP_SAMPLES,
+                samples_tested: 0,
+                longest_run: 0,
+                max_window_count: 0,
+            },
+            failed: false,
+            last: None,
+            run: 0,
+            window_value: 0,
+            window_seen: 0,
+            window_count: 0,
+        }
+    }
+
+    /// Fill `buf` with tested bytes from the kernel CSPRNG.
+    pub fn fill(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        if self.failed {
+            return Err(health_failure("source disabled after an earlier failure".to_string()));
+        }
+        getrandom::getrandom(buf).map_err(|e| io::Error::other(format!("Kernel CSPRNG unavailable: {}", e)))?;
+        self.check(buf)
+    }
+
+    /// ChaCha20 stream keyed with 32 tested bytes, for bulk data (free-space
+    /// fill, file overwrites) where pulling every byte through the tests
+    /// would be needlessly slow.
+    pub fn stream(&mut self) -> io::Result<ChaCha20Rng> {
+        let mut seed = [0u8; 32];
+        self.fill(&mut seed)?;
+        let rng = ChaCha20Rng::from_seed(seed);
+        seed.zeroize();
+        Ok(rng)
+    }
+
+    fn check(&mut self, buf: &[u8]) -> io::Result<()> {
+        if self.failed {
+            return Err(health_failure("source disabled after an earlier failure".to_string()));
+        }
+        for &sample in buf {
+            if let Err(e) = self.test(sample) {
+                self.failed = true;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    fn test(&mut self, sample: u8) -> io::Result<()> {
+        self.status.samples_tested += 1;
+
+        // Repetition count test (SP 800-90B 4.4.1)
+        if self.last == Some(sample) {
+            self.run += 1;
+        } else {
+            self.last = Some(sample);
+            self.run = 1;
+        }
+        self.status.longest_run = self.status.longest_run.max(self.run);
+        if self.run >= RCT_CUTOFF {
+            return Err(health_failure(format!("byte 0x{:02x} repeated {} times in a row", sample, self.run)));
+        }
+
+        // Adaptive proportion test (SP 800-90B 4.4.2)
+        if self.window_seen == 0 {
+            self.window_value = sample;
+            self.window_count = 1;
+        } else if sample == self.window_value {
+            self.window_count += 1;
+        }
+        self.window_seen += 1;
+        self.status.max_window_count = self.status.max_window_count.max(self.window_count);
+        if self.window_count >= APT_CUTOFF {
+            return Err(health_failure(format!("byte 0x{:02x} occurred {} times in a {}-byte window",
+                self.window_value, self.window_count, APT_WINDOW)));
+        }
+        if self.window_seen == APT_WINDOW {
+            self.window_seen = 0;
+        }
+        Ok(())
+    }
+
+    pub fn status(&self) -> EntropyStatus {
+        self.status.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    #[test]
+    fn stuck_source_fails_the_repetition_count_test() {
+        let mut source = EntropySource::new(Duration::ZERO);
+        // One repetition short of the cutoff still passes
+        assert!(source.check(&[0x10, 0x42, 0x42, 0x42, 0x42, 0x42, 0x11]).is_ok());
+        assert_eq!(source.status().longest_run, RCT_CUTOFF - 1);
+
+        let error = source.check(&[0x42; RCT_CUTOFF as usize]).unwrap_err();
+        assert!(error.to_string().contains("repeated 6 times"), "{}", error);
+        // Once failed, the source stays disabled
+        assert!(source.check(&[0x01, 0x02]).is_err());
+    }
+
+    #[test]
+    fn biased_source_fails_the_adaptive_proportion_test() {
+        // 0xaa on every other byte never repeats but makes up half the window
+        let biased: Vec<u8> = (0..APT_WINDOW as usize)
+            .map(|i| if i % 2 == 0 { 0xaa } else { i as u8 })
+            .collect();
+        let mut source = EntropySource::new(Duration::ZERO);
+        let error = source.check(&biased).unwrap_err();
+        assert!(error.to_string().contains("occurred 20 times in a 512-byte window"), "{}", error);
+        assert_eq!(source.status().samples_tested, 2 * (APT_CUTOFF as u64 - 1) + 1);
+
+        // One short of the cutoff in each window passes: the count restarts
+        // with every window
+        let (window, cutoff) = (APT_WINDOW as usize, APT_CUTOFF as usize);
+        let spread: Vec<u8> = (0..2 * window)
+            .map(|i| if i % 2 == 0 && i % window < 2 * (cutoff - 1) { 0xaa } else { i as u8 | 1 })
+            .collect();
+        let mut source = EntropySource::new(Duration::ZERO);
+        source.check(&spread).unwrap();
+        assert_eq!(source.status().max_window_count, APT_CUTOFF - 1);
+    }
+
+    #[test]
+    fn good_source_passes() {
+        let mut data = vec![0u8; 1 << 20];
+        ChaCha20Rng::from_seed([3u8; 32]).fill_bytes(&mut data);
+        let mut source = EntropySource::new(Duration::ZERO);
+        source.check(&data).unwrap();
+        let status = source.status();
+        assert_eq!(status.samples_tested, data.len() as u64);
+        assert!(status.longest_run < RCT_CUTOFF);
+        assert!(status.max_window_count < APT_CUTOFF);
+
+        // And the kernel CSPRNG passes start-up and continuous testing
+        let mut source = EntropySource::start().unwrap();
+        source.fill(&mut data).unwrap();
+    }
+}
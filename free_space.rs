@@ -0,0 +1,238 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+use std::time::{Duration, Instant};
+use rand::RngCore;
+use uuid::Uuid;
+use crate::entropy::EntropySource;
+
+// Largest chunk written to the fill file; shrunk towards MIN_CHUNK once the
+// filesystem reports ENOSPC so the last partial blocks get covered too
+const FILL_CHUNK: usize = 8 * 1024 * 1024;
+const MIN_CHUNK: usize = 4096;
+const ENOSPC: i32 = 28;
+const EFBIG: i32 = 27;
+
+pub struct FreeSpaceReport {
+    pub operation_id: Uuid,
+    pub mount_point: String,
+    pub bytes_covered: u64,
+    pub trimmed: Option<String>,
+    pub entropy: String,
+    pub duration: Duration,
+}
+
+/// Resolve a mount point from either a directory or a mounted block device.
+pub fn resolve_mount_point(target: &str) -> io::Result<String> {
+    let path = Path::new(target);
+    if path.is_dir() {
+        let output = ProcessCommand::new("findmnt")
+            .args(["-n", "-o", "TARGET", "--target", target])
+            .output()?;
+        if output.status.success() && !output.stdout.is_empty() {
+            return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+        }
+        return Ok(target.to_string());
+    }
+
+    let output = ProcessCommand::new("findmnt")
+        .args(["-n", "-o", "TARGET", "--source", target])
+        .output()?;
+
+    let mount_point = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_string();
+
+    if !output.status.success() || mount_point.is_empty() {
+        return Err(io::Error::other(
+            format!("{} is not mounted - free-space wipe needs a mounted filesystem", target)
+        ));
+    }
+
+    Ok(mount_point)
+}
+
+fn available_bytes(mount_point: &str) -> Option<u64> {
+    let output = ProcessCommand::new("df")
+        .args(["-B1", "--output=avail", mount_point])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .nth(1)
+        .and_then(|line| line.trim().parse().ok())
+}
+
+pub fn wipe_free_space(target: &str) -> io::Result<FreeSpaceReport> {
+    let operation_id = Uuid::new_v4();
+    let started = Instant::now();
+    let mount_point = resolve_mount_point(target)?;
+
+    println!("🚀 Starting free-space wipe...");
+    println!("🆔 Operation ID: {}", operation_id);
+    println!("📂 Filesystem: {}", mount_point);
+    if let Some(avail) = available_bytes(&mount_point) {
+        println!("💾 Free space reported: {:.2} GiB", avail as f64 / (1u64 << 30) as f64);
+    }
+    println!();
+
+    println!("🎲 Checking the kernel random source...");
+    let mut entropy = EntropySource::start()?;
+    println!("✅ {}", entropy.status().describe());
+
+    let fill_prefix = PathBuf::from(&mount_point).join(format!(".wipeshit-fill-{}", operation_id.simple()));
+    println!("📝 Step 1: Filling free space with random data...");
+    let mut fill_files = Vec::new();
+    let fill_result = fill_until_full(&fill_prefix, &mut fill_files, &mut entropy);
+
+    // Always try to remove the fill files, even if the fill itself failed,
+    // so the filesystem is never left full
+    println!("\n🗑️  Step 2: Removing fill files...");
+    let remove_result = fill_files.iter().map(fs::remove_file).fold(Ok(()), io::Result::and);
+    let bytes_covered = fill_result?;
+    remove_result?;
+    sync_filesystem(&mount_point)?;
+    println!("✅ {} fill files removed ({} bytes covered)", fill_files.len(), bytes_covered);
+
+    println!("\n✂️  Step 3: Discarding freed blocks...");
+    let trimmed = trim_filesystem(&mount_point)?;
+
+    Ok(FreeSpaceReport {
+        operation_id,
+        mount_point,
+        bytes_covered,
+        trimmed,
+        entropy: entropy.status().describe(),
+        duration: started.elapsed(),
+    })
+}
+
+fn create_fill_file(prefix: &Path, files: &mut Vec<PathBuf>) -> io::Result<File> {
+    let mut name = prefix.as_os_str().to_owned();
+    name.push(format!("-{}", files.len()));
+    let path = PathBuf::from(name);
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    files.push(path);
+    Ok(file)
+}
+
+/// Write random data until the filesystem is full. Filesystems with a file
+/// size limit (4 GiB on FAT32) get as many fill files as it takes; every file
+/// created is added to `files` so the caller can remove them.
+fn fill_until_full(prefix: &Path, files: &mut Vec<PathBuf>, entropy: &mut EntropySource) -> io::Result<u64> {
+    let mut file = create_fill_file(prefix, files)?;
+    let mut rng = entropy.stream()?;
+    let mut buffer = vec![0u8; FILL_CHUNK];
+    let mut chunk = FILL_CHUNK;
+    let mut written: u64 = 0;
+    let mut file_written: u64 = 0;
+    let mut last_report = Instant::now();
+
+    loop {
+        rng.fill_bytes(&mut buffer[..chunk]);
+        match file.write(&buffer[..chunk]) {
+            Ok(0) => break,
+            Ok(n) => {
+                written += n as u64;
+                file_written += n as u64;
+            }
+            Err(e) if e.raw_os_error() == Some(ENOSPC) => {
+                if chunk <= MIN_CHUNK {
+                    break;
+                }
+                chunk /= 2;
+                continue;
+            }
+            Err(e) if e.raw_os_error() == Some(EFBIG) || e.kind() == io::ErrorKind::FileTooLarge => {
+                if file_written == 0 {
+                    return Err(e);
+                }
+                // This file is at the size limit; carry on in a new one
+                file.sync_all()?;
+                file = create_fill_file(prefix, files)?;
+                file_written = 0;
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+
+        if last_report.elapsed() >= Duration::from_secs(1) {
+            print!("\r   {:.2} GiB written", written as f64 / (1u64 << 30) as f64);
+            io::stdout().flush()?;
+            last_report = Instant::now();
+        }
+    }
+
+    // Data must reach the disk before the files are unlinked, otherwise the
+    // page cache absorbs the writes and the blocks are never touched
+    file.sync_all()?;
+    println!("\r   {:.2} GiB written", written as f64 / (1u64 << 30) as f64);
+    println!("✅ Free space filled");
+    Ok(written)
+}
+
+fn sync_filesystem(mount_point: &str) -> io::Result<()> {
+    let status = ProcessCommand::new("sync")
+        .args(["-f", mount_point])
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(
+            format!("sync -f {} failed - the fill may not have reached the disk", mount_point)
+        ));
+    }
+    Ok(())
+}
+
+/// Discard the freed blocks. A missing fstrim or a filesystem/device without
+/// discard support is not an error; any other fstrim failure is.
+fn trim_filesystem(mount_point: &str) -> io::Result<Option<String>> {
+    let output = match ProcessCommand::new("fstrim").args(["-v", mount_point]).output() {
+        Ok(output) => output,
+        Err(_) => {
+            println!("⚠️  fstrim not available - skipping discard");
+            return Ok(None);
+        }
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if output.status.success() {
+        let summary = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        println!("✅ {}", summary);
+        Ok(Some(summary))
+    } else if stderr.contains("not supported") {
+        println!("⚠️  Discard not supported on this filesystem/device: {}", stderr);
+        Ok(None)
+    } else {
+        Err(io::Error::other(
+            format!("fstrim {} failed: {}", mount_point, stderr)
+        ))
+    }
+}
+
+pub fn print_free_space_report(report: &FreeSpaceReport) {
+    let separator = "═".repeat(60);
+    println!("\n{}", separator);
+    println!("📋 FREE-SPACE WIPE COMPLETION REPORT");
+    println!("{}", separator);
+    println!("🆔 Operation ID: {}", report.operation_id);
+    println!("📂 Filesystem: {}", report.mount_point);
+    println!("🎲 Method: Random data fill of all free space (ChaCha20 stream from a health-tested seed)");
+    println!("🎲 Entropy: {}", report.entropy);
+    println!("📏 Bytes covered: {} ({:.2} GiB)",
+        report.bytes_covered,
+        report.bytes_covered as f64 / (1u64 << 30) as f64);
+    match &report.trimmed {
+        Some(summary) => println!("✂️  Discard: {}", summary),
+        None => println!("✂️  Discard: not supported"),
+    }
+    println!("⏱️  Duration: {}s", report.duration.as_secs());
+    println!("ℹ️  Existing files were left untouched");
+    println!("{}", separator);
+}
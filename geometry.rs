@@ -0,0 +1,167 @@
+use std::fs::File;
+use std::io;
+use std::os::unix::io::AsRawFd;
+
+// Block device ioctls from <linux/fs.h>
+const BLKSSZGET: libc::c_ulong = 0x1268;
+const BLKIOMIN: libc::c_ulong = 0x1278;
+const BLKIOOPT: libc::c_ulong = 0x1279;
+const BLKALIGNOFF: libc::c_ulong = 0x127A;
+const BLKPBSZGET: libc::c_ulong = 0x127B;
+
+/// Sector sizes and I/O hints the kernel reports for a block device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SectorGeometry {
+    /// Smallest addressable unit (512 on 512n/512e, 4096 on 4Kn).
+    pub logical: u64,
+    /// Unit the media actually writes; 4096 on 512e drives.
+    pub physical: u64,
+    /// Smallest I/O without a read-modify-write penalty.
+    pub minimum_io: u64,
+    /// Preferred I/O size (e.g. RAID stripe width); 0 if not reported.
+    pub optimal_io: u64,
+    /// Offset of the first physically aligned logical sector, in bytes.
+    pub alignment_offset: u64,
+}
+
+impl Default for SectorGeometry {
+    fn default() -> Self {
+        SectorGeometry { logical: 512, physical: 512, minimum_io: 512, optimal_io: 0, alignment_offset: 0 }
+    }
+}
+
+fn ioctl_int(file: &File, request: libc::c_ulong) -> io::Result<u64> {
+    let mut value: libc::c_int = 0;
+    if unsafe { libc::ioctl(file.as_raw_fd(), request, &mut value) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(value.max(0) as u64)
+}
+
+fn ioctl_uint(file: &File, request: libc::c_ulong) -> io::Result<u64> {
+    let mut value: libc::c_uint = 0;
+    if unsafe { libc::ioctl(file.as_raw_fd(), request, &mut value) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(value as u64)
+}
+
+/// Query BLKSSZGET/BLKPBSZGET/BLKIOMIN/BLKIOOPT/BLKALIGNOFF.
+pub fn read_geometry(device: &str) -> io::Result<SectorGeometry> {
+    let file = File::open(device)?;
+    let logical = ioctl_int(&file, BLKSSZGET)?;
+    let physical = ioctl_uint(&file, BLKPBSZGET).unwrap_or(logical);
+
+    Ok(SectorGeometry {
+        logical,
+        physical: physical.max(logical),
+        minimum_io: ioctl_uint(&file, BLKIOMIN).unwrap_or(physical).max(logical),
+        optimal_io: ioctl_uint(&file, BLKIOOPT).unwrap_or(0),
+        alignment_offset: ioctl_int(&file, BLKALIGNOFF).unwrap_or(0),
+    })
+}
+
+impl SectorGeometry {
+    /// Largest size up to `limit` that is a whole number of optimal (or,
+    /// failing that, physical) I/O units.
+    pub fn io_size(&self, limit: u64) -> u64 {
+        let unit = if self.optimal_io >= self.physical { self.optimal_io } else { self.physical };
+        if unit == 0 || unit > limit {
+            return limit - limit % self.logical;
+        }
+        limit - limit % unit
+    }
+
+    /// Whether a byte offset starts on a physical sector boundary.
+    pub fn is_physically_aligned(&self, offset: u64) -> bool {
+        offset % self.physical == self.alignment_offset % self.physical
+    }
+
+    pub fn format_type(&self) -> &'static str {
+        match (self.logical, self.physical) {
+            (512, 512) => "512n",
+            (512, 4096) => "512e",
+            (4096, 4096) => "4Kn",
+            _ => "other",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!("logical {} B, physical {} B ({}), min I/O {} B, optimal I/O {}, alignment offset {} B",
+            self.logical,
+            self.physical,
+            self.format_type(),
+            self.minimum_io,
+            if self.optimal_io > 0 { format!("{} B", self.optimal_io) } else { "not reported".to_string() },
+            self.alignment_offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1 << 20;
+
+    fn geometry(logical: u64, physical: u64, optimal_io: u64, alignment_offset: u64) -> SectorGeometry {
+        SectorGeometry { logical, physical, minimum_io: physical, optimal_io, alignment_offset }
+    }
+
+    #[test]
+    fn io_size_without_optimal_io_rounds_to_physical_sectors() {
+        // 512n
+        let native = geometry(512, 512, 0, 0);
+        assert_eq!(native.io_size(8 * MIB), 8 * MIB);
+        assert_eq!(native.io_size(8 * MIB + 100), 8 * MIB);
+        assert_eq!(native.io_size(1000), 512);
+
+        // 512e: whole 4 KiB physical sectors, avoiding read-modify-write
+        let emulated = geometry(512, 4096, 0, 0);
+        assert_eq!(emulated.format_type(), "512e");
+        assert_eq!(emulated.io_size(8 * MIB + 512), 8 * MIB);
+        assert_eq!(emulated.io_size(6000), 4096);
+        // Below one physical sector only logical alignment is possible
+        assert_eq!(emulated.io_size(3000), 2560);
+
+        // 4Kn
+        let four_k = geometry(4096, 4096, 0, 0);
+        assert_eq!(four_k.format_type(), "4Kn");
+        assert_eq!(four_k.io_size(10000), 8192);
+        assert_eq!(four_k.io_size(4096), 4096);
+    }
+
+    #[test]
+    fn io_size_prefers_the_optimal_io_size() {
+        // RAID stripe of 1 MiB
+        let striped = geometry(512, 4096, MIB, 0);
+        assert_eq!(striped.io_size(8 * MIB + 4096), 8 * MIB);
+        // A stripe width that does not divide the limit: three 64 KiB chunks
+        let three = geometry(4096, 4096, 3 * 64 * 1024, 0);
+        assert_eq!(three.io_size(8 * MIB), 42 * 3 * 64 * 1024);
+        // Larger than the limit: fall back to logical sectors
+        assert_eq!(three.io_size(100_000), 98_304);
+        // Smaller than a physical sector (bogus hint): physical sectors win
+        let bogus = geometry(512, 4096, 512, 0);
+        assert_eq!(bogus.io_size(6000), 4096);
+    }
+
+    #[test]
+    fn physical_alignment_honours_the_alignment_offset() {
+        let native = geometry(512, 512, 0, 0);
+        assert!(native.is_physically_aligned(0));
+        assert!(native.is_physically_aligned(512));
+
+        let emulated = geometry(512, 4096, 0, 0);
+        assert!(emulated.is_physically_aligned(0));
+        assert!(emulated.is_physically_aligned(MIB));
+        assert!(!emulated.is_physically_aligned(512));
+        assert!(!emulated.is_physically_aligned(3584));
+
+        // Partition starting at sector 63 of a 512e drive: 63 * 512 % 4096
+        let shifted = geometry(512, 4096, 0, 3584);
+        assert!(shifted.is_physically_aligned(3584));
+        assert!(shifted.is_physically_aligned(3584 + 4096));
+        assert!(!shifted.is_physically_aligned(0));
+        assert!(!shifted.is_physically_aligned(4096));
+    }
+}
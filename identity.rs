@@ -0,0 +1,158 @@
+use std::process::Command as ProcessCommand;
+
+/// What identifies a physical drive independently of its /dev name, which
+/// can change between boots or docks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceIdentity {
+    pub model: String,
+    pub serial: String,
+    pub size_bytes: u64,
+}
+
+fn lsblk_field(device: &str, field: &str) -> String {
+    ProcessCommand::new("lsblk")
+        .args(["-d", "-n", "-b", "-o", field, device])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+pub fn read_identity(device: &str) -> DeviceIdentity {
+    let model = lsblk_field(device, "MODEL");
+    let serial = lsblk_field(device, "SERIAL");
+    let size_bytes = lsblk_field(device, "SIZE").parse().unwrap_or(0);
+
+    DeviceIdentity {
+        model: if model.is_empty() { "Unknown".to_string() } else { model },
+        serial: if serial.is_empty() { "Unknown".to_string() } else { serial },
+        size_bytes,
+    }
+}
+
+/// Make and kind of a drive, for the certificate. Not part of the identity,
+/// since the vendor lsblk reports depends on the bridge a drive is behind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaInfo {
+    pub manufacturer: String,
+    /// NIST SP 800-88 media type ("Magnetic", "Flash Memory") with the kind of drive
+    pub media_type: String,
+}
+
+impl Default for MediaInfo {
+    fn default() -> Self {
+        MediaInfo { manufacturer: "Unknown".to_string(), media_type: "Unknown".to_string() }
+    }
+}
+
+// Model prefixes of drives whose vendor field only names the bus ("ATA")
+const MODEL_MANUFACTURERS: &[(&str, &str)] = &[
+    ("Samsung", "Samsung"),
+    ("WDC", "Western Digital"),
+    ("WD", "Western Digital"),
+    ("ST", "Seagate"),
+    ("TOSHIBA", "Toshiba"),
+    ("HGST", "HGST"),
+    ("Hitachi", "Hitachi"),
+    ("KINGSTON", "Kingston"),
+    ("Crucial", "Crucial"),
+    ("CT", "Crucial"),
+    ("INTEL", "Intel"),
+    ("SanDisk", "SanDisk"),
+    ("Micron", "Micron"),
+    ("SK hynix", "SK hynix"),
+];
+
+pub fn read_media_info(device: &str) -> MediaInfo {
+    let vendor = lsblk_field(device, "VENDOR");
+    let model = lsblk_field(device, "MODEL");
+    let manufacturer = if !vendor.is_empty() && vendor != "ATA" {
+        vendor
+    } else {
+        MODEL_MANUFACTURERS.iter()
+            .find(|(prefix, _)| model.to_lowercase().starts_with(&prefix.to_lowercase()))
+            .map(|(_, name)| name.to_string())
+            .unwrap_or_else(|| "Unknown".to_string())
+    };
+
+    let transport = lsblk_field(device, "TRAN");
+    let media_type = match (transport.as_str(), lsblk_field(device, "ROTA").as_str()) {
+        ("nvme", _) => "Flash Memory (NVMe SSD)",
+        (_, "1") => "Magnetic (hard disk drive)",
+        _ if device.starts_with("/dev/mmcblk") => "Flash Memory (memory card)",
+        ("usb", "0") => "Flash Memory (USB)",
+        (_, "0") => "Flash Memory (SSD)",
+        _ => "Unknown",
+    };
+
+    MediaInfo { manufacturer, media_type: media_type.to_string() }
+}
+
+impl DeviceIdentity {
+    /// Whether the serial number tells this drive apart. Partitions, dm
+    /// devices and many USB bridges report none and all read as "Unknown".
+    pub fn has_serial(&self) -> bool {
+        !self.serial.is_empty() && self.serial != "Unknown"
+    }
+
+    /// Stable file-name friendly key for this drive.
+    pub fn key(&self) -> String {
+        let raw = format!("{}_{}_{}", self.model, self.serial, self.size_bytes);
+        raw.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect()
+    }
+}
+
+/// World Wide Name of a drive, where the drive reports one.
+pub fn read_wwn(device: &str) -> Option<String> {
+    let wwn = lsblk_field(device, "WWN");
+    if wwn.is_empty() { None } else { Some(wwn) }
+}
+
+/// Where a drive recorded earlier is now.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    Found(String),
+    NotConnected,
+    /// The drive cannot be told apart from others; an operator has to decide
+    NeedsAttention(String),
+}
+
+/// Find the current /dev path of a drive, whose name may have changed since
+/// the identity was recorded (reboot, different dock). Only whole disks with
+/// a real serial number (and the same WWN, if one was recorded) are searched
+/// for: partitions, dm devices and many USB bridges report no serial, and
+/// several of them can share a model and size.
+pub fn find_device(identity: &DeviceIdentity, wwn: Option<&str>, last_known_path: &str) -> Resolution {
+    let matches = |path: &str| {
+        read_identity(path) == *identity && wwn.is_none_or(|wwn| read_wwn(path).as_deref() == Some(wwn))
+    };
+    if std::path::Path::new(last_known_path).exists() && matches(last_known_path) {
+        return Resolution::Found(last_known_path.to_string());
+    }
+    if !identity.has_serial() {
+        return Resolution::NeedsAttention(format!(
+            "{} no longer holds this drive, and it has no serial number to find it by", last_known_path
+        ));
+    }
+
+    let output = match ProcessCommand::new("lsblk").args(["-n", "-l", "-o", "PATH,TYPE"]).output() {
+        Ok(output) => output,
+        Err(_) => return Resolution::NotConnected,
+    };
+    let candidates: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(_, kind)| kind.trim() == "disk")
+        .map(|(path, _)| path.to_string())
+        .filter(|path| matches(path))
+        .collect();
+
+    match candidates.as_slice() {
+        [] => Resolution::NotConnected,
+        [path] => Resolution::Found(path.clone()),
+        _ => Resolution::NeedsAttention(format!(
+            "{} connected drives match (model, serial, size): {}", candidates.len(), candidates.join(", ")
+        )),
+    }
+}
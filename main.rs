@@ -1,18 +1,80 @@
 use std::env;
-use std::process::{Command as ProcessCommand, Stdio};
+use std::process::Command as ProcessCommand;
 use std::io::{self, Write};
 use std::path::Path;
 use uuid::Uuid;
-use rand::{thread_rng, Rng};
-use std::time::{SystemTime, UNIX_EPOCH, Duration};
+use std::time::Duration;
 use std::thread;
 use std::fs;
 
+mod free_space;
+mod shred;
+mod provision;
+mod parallel;
+mod identity;
+mod checkpoint;
+mod write_engine;
+mod queue;
+mod throttle;
+mod range;
+mod geometry;
+mod cipher;
+mod report;
+mod secret;
+mod entropy;
+mod verify;
+mod pattern;
+mod residue;
+mod audit;
+mod certificate;
+mod pdf;
+mod signing;
+mod disposition;
+
+use provision::ProvisionSpec;
+use checkpoint::{Checkpoint, CheckpointStore, ResumeEvent};
+use throttle::Throttle;
+use range::{ByteRange, RangeMapping};
+use geometry::SectorGeometry;
+use cipher::{DetachedHeader, LuksParams};
+use report::WipeRecord;
+use secret::SecretKey;
+use verify::{Expected, VerifyPlan};
+use pattern::Pattern;
+use disposition::{Confidentiality, Destination, Disposition};
+
 fn show_help() {
     println!("LUKS Crypto Wipe v1.0 - Secure Data Destruction Tool");
     println!();
     println!("USAGE:");
     println!("    wipeshit [DEVICE] [OPTIONS]");
+    println!("    wipeshit wipe <DEVICE>... [OPTIONS]");
+    println!("    wipeshit free-space <MOUNTPOINT|DEVICE> [OPTIONS]");
+    println!("    wipeshit shred <PATH>... [OPTIONS]");
+    println!("    wipeshit audit <DEVICE>... [--verify-coverage P] [--residue-scan MODE]");
+    println!("    wipeshit schema");
+    println!("    wipeshit verify-cert <CERTIFICATE.json>... --expect-fingerprint FP | --trusted-keys FILE");
+    println!("    wipeshit queue add <DEVICE>... [--priority N] [OPTIONS]");
+    println!("    wipeshit queue list | run [--concurrency N] [--drain] | remove <JOB_ID>");
+    println!();
+    println!("COMMANDS:");
+    println!("    wipe        Wipe one or more devices; several devices are wiped in");
+    println!("                parallel, each with its own operation ID and report");
+    println!("    free-space  Fill the free space of a mounted filesystem with random data,");
+    println!("                then remove the fill file and discard freed blocks.");
+    println!("                Existing files are kept.");
+    println!("    shred       Overwrite files in place (every extent found via FIEMAP),");
+    println!("                scramble their names, truncate and delete them.");
+    println!("                Directories are shredded recursively.");
+    println!("    audit       Check a drive someone else wiped, strictly read-only: partition");
+    println!("                and filesystem signatures, sampled content analysis and a");
+    println!("                residue scan (sampled by default), ending in a pass/fail verdict");
+    println!("    schema      Print the JSON Schema of wipe certificates");
+    println!("    verify-cert Check offline that JSON certificates carry a valid Ed25519");
+    println!("                signature of a trusted station (--expect-fingerprint or --trusted-keys)");
+    println!("    queue       Persistent wipe queue for wipe stations: 'add' submits jobs,");
+    println!("                'run' dispatches them by priority with a concurrency limit");
+    println!("                and picks up interrupted jobs after a restart.");
     println!();
     println!("ARGUMENTS:");
     println!("    <DEVICE>    Target device to wipe (e.g., /dev/sdb)");
@@ -20,7 +82,52 @@ fn show_help() {
     println!();
     println!("OPTIONS:");
     println!("    -f, --force     Force wipe without confirmation");
-    println!("    -v, --verify    Verify the wipe operation");
+    println!("    -v, --verify    Verify the wipe by reading back samples spread over the");
+    println!("                    whole device (statistical test for random data, exact");
+    println!("                    match for known fills)");
+    println!("    --verify-coverage <PERCENT>  Share of the device to sample (default: {}%)", verify::DEFAULT_COVERAGE);
+    println!("    --verify-full   Read the whole device back and compare it with the written");
+    println!("                    pattern, listing every mismatching range (overwrite methods)");
+    println!("    --residue-scan <MODE>  After wiping, search sampled (sampled, at the");
+    println!("                    --verify-coverage share) or all (full) sectors for leftover");
+    println!("                    file signatures: images, PDF, Office, SQLite, NTFS, ext4, LUKS...");
+    println!("    --provision <FS>    After wiping, create a partition table and an");
+    println!("                        exfat, ntfs or ext4 filesystem for resale");
+    println!("    --layout <TABLE>    Partition table for --provision: gpt (default) or mbr");
+    println!("    --label <LABEL>     Filesystem label for --provision (default: {})", provision::DEFAULT_LABEL);
+    println!("    --range START:END   Wipe only this extent of the device. Bytes, or with a");
+    println!("                        suffix: s (logical sectors), K, M, G, T. END is exclusive;");
+    println!("                        leave it empty to wipe to the end of the device");
+    println!("    --max-rate <MB/s>   Limit the write rate of each wipe job");
+    println!("    --bus-rate <MB/s>   Limit the combined write rate of all jobs on the same");
+    println!("                        USB bus / storage controller");
+    println!("    --method <METHOD>   luks (default): LUKS2 header with a throwaway key;");
+    println!("                        detached: LUKS2 with the header kept in a tmpfs file;");
+    println!("                        plain: dm-crypt with a random key from the kernel, no header;");
+    println!("                        zero, ones, random: single overwrite pass with 0x00, 0xFF or");
+    println!("                        a seeded ChaCha20 stream, written straight to the device");
+    println!("    --cipher <SPEC>     dm-crypt cipher for the fill (default: aes-xts-plain64);");
+    println!("                        'auto' benchmarks AES-XTS key sizes and picks the fastest");
+    println!("    --key-size <BITS>   Key size (default: 512 for XTS, 256 otherwise)");
+    println!("    --hash <HASH>       LUKS header hash (default: sha256; LUKS only)");
+    println!("    --pbkdf <KDF>       argon2id (default), argon2i, pbkdf2, or minimal for the");
+    println!("                        cheapest derivation - the key is thrown away anyway (LUKS only)");
+    println!("    --resume            Continue an interrupted wipe from its last checkpoint");
+    println!("    --checkpoint-dir <DIR>  Where checkpoints are kept; must not be on the target");
+    println!("                        (default: {})", checkpoint::DEFAULT_CHECKPOINT_DIR);
+    println!("    --certificate-dir <DIR>  Where JSON wipe certificates are written");
+    println!("                        (default: {})", certificate::DEFAULT_CERTIFICATE_DIR);
+    println!("    --operator <NAME>   Operator named in the certificate (default: the sudo user)");
+    println!("    --validator <NAME>  Person who validates the wipe and countersigns the certificate");
+    println!("    --media-source <TEXT>  Owner or asset tag of the drive, for the certificate");
+    println!("    --confidentiality <CATEGORY>  Confidentiality of the data before the wipe:");
+    println!("                        low, moderate or high (FIPS 199)");
+    println!("    --destination <DEST>  Where the drive goes afterwards: internal-reuse,");
+    println!("                        external-reuse, recycling, manufacturer or other");
+    println!("    --station-key <PATH>  Ed25519 key certificates are signed with, created on");
+    println!("                        first use (default: {})", signing::DEFAULT_STATION_KEY);
+    println!("    --expect-fingerprint <FP>  verify-cert: the station fingerprint to trust");
+    println!("    --trusted-keys <FILE>  verify-cert: file of trusted fingerprints, one per line");
     println!("    -h, --help      Show this help message");
     println!();
     println!("EXAMPLES:");
@@ -28,6 +135,18 @@ fn show_help() {
     println!("    wipeshit /dev/sdb           # Wipe specific device");
     println!("    wipeshit /dev/sdb --force   # Force wipe without confirmation");
     println!("    wipeshit /dev/sdb --verify  # Wipe with verification");
+    println!("    wipeshit /dev/sdb --method random --verify-full  # Overwrite, read all back");
+    println!("    wipeshit /dev/sdb --residue-scan full  # Wipe, then look for leftover files");
+    println!("    wipeshit /dev/sdb --provision exfat  # Wipe, then format for resale");
+    println!("    wipeshit wipe /dev/sdb /dev/sdc      # Wipe two drives in parallel");
+    println!("    wipeshit /dev/sdb --cipher auto --pbkdf minimal  # Fastest AES-XTS fill");
+    println!("    wipeshit wipe /dev/sdb --range 2048s:4196352s  # Wipe a sector range");
+    println!("    wipeshit queue add /dev/sdb --priority 5 --verify");
+    println!("    wipeshit queue run --concurrency 8   # Long-running dispatcher");
+    println!("    wipeshit free-space /       # Wipe deleted data, keep the OS");
+    println!("    wipeshit shred ~/export.xlsx  # Securely delete a file");
+    println!("    wipeshit audit /dev/sdb --residue-scan full  # Check a partner's wipe");
+    println!("    wipeshit verify-cert 3f2c...json --expect-fingerprint SHA256:699f...  # Check a certificate");
     println!();
     println!("WARNING: This tool will PERMANENTLY destroy ALL data on the target device!");
 }
@@ -36,9 +155,92 @@ fn main() -> io::Result<()> {
     let args: Vec<String> = env::args().collect();
     
     // Parse simple command line arguments
-    let device = if args.len() > 1 { Some(args[1].clone()) } else { None };
+    let wipe_command = args.len() > 1 && args[1] == "wipe";
+    let devices = positional_args(&args[if wipe_command { 2 } else { 1 }..]);
     let force = args.contains(&"--force".to_string()) || args.contains(&"-f".to_string());
     let verify = args.contains(&"--verify".to_string()) || args.contains(&"-v".to_string());
+    let provision = match option_value(&args, "--provision") {
+        Some(fs) => Some(ProvisionSpec::new(
+            &fs,
+            option_value(&args, "--layout").as_deref(),
+            option_value(&args, "--label").as_deref(),
+        )?),
+        None => None,
+    };
+    let cipher = option_value(&args, "--cipher");
+    let auto_cipher = cipher.as_deref() == Some("auto");
+    let luks = LuksParams::new(
+        cipher.as_deref().filter(|_| !auto_cipher),
+        option_value(&args, "--key-size").as_deref(),
+        option_value(&args, "--hash").as_deref(),
+        option_value(&args, "--pbkdf").as_deref(),
+    )?;
+    let method = match option_value(&args, "--method") {
+        Some(value) => WipeMethod::parse(&value)?,
+        None => WipeMethod::Luks2,
+    };
+    let verify_coverage = match option_value(&args, "--verify-coverage") {
+        Some(value) => match value.trim_end_matches('%').parse::<f64>() {
+            Ok(percent) if percent > 0.0 && percent <= 100.0 => percent,
+            _ => {
+                eprintln!("❌ Error: --verify-coverage needs a percentage between 0 and 100");
+                return Ok(());
+            }
+        },
+        None => verify::DEFAULT_COVERAGE,
+    };
+    let verify_full = args.contains(&"--verify-full".to_string());
+    if verify_full && !method.is_overwrite() {
+        eprintln!("❌ Error: --verify-full needs an overwrite method (--method zero, ones or random)");
+        return Ok(());
+    }
+    let residue_scan = match option_value(&args, "--residue-scan") {
+        Some(value) => Some(residue::ScanMode::parse(&value)?),
+        None => None,
+    };
+    let disposition = Disposition {
+        operator: option_value(&args, "--operator").unwrap_or_else(certificate::default_operator),
+        validator: option_value(&args, "--validator"),
+        media_source: option_value(&args, "--media-source"),
+        confidentiality: match option_value(&args, "--confidentiality") {
+            Some(value) => Some(Confidentiality::parse(&value)?),
+            None => None,
+        },
+        destination: match option_value(&args, "--destination") {
+            Some(value) => Some(Destination::parse(&value)?),
+            None => None,
+        },
+    };
+    let options = WipeOptions {
+        method,
+        // Asking for a coverage implies verification
+        verify: verify || verify_full || option_value(&args, "--verify-coverage").is_some(),
+        verify_coverage,
+        verify_full,
+        residue_scan,
+        provision,
+        luks,
+        auto_cipher,
+        resume: args.contains(&"--resume".to_string()),
+        checkpoint_dir: option_value(&args, "--checkpoint-dir")
+            .unwrap_or_else(|| checkpoint::DEFAULT_CHECKPOINT_DIR.to_string()),
+        max_rate: rate_option(&args, "--max-rate")?,
+        bus_rate: rate_option(&args, "--bus-rate")?,
+        range: option_value(&args, "--range"),
+        certificate_dir: option_value(&args, "--certificate-dir")
+            .unwrap_or_else(|| certificate::DEFAULT_CERTIFICATE_DIR.to_string()),
+        disposition,
+        station_key: option_value(&args, "--station-key")
+            .unwrap_or_else(|| signing::DEFAULT_STATION_KEY.to_string()),
+    };
+    if options.range.is_some() && options.provision.is_some() {
+        eprintln!("❌ Error: --provision cannot be combined with --range");
+        return Ok(());
+    }
+    if auto_cipher && option_value(&args, "--key-size").is_some() {
+        eprintln!("❌ Error: --key-size cannot be combined with --cipher auto");
+        return Ok(());
+    }
     
     // Show help if requested
     if args.contains(&"--help".to_string()) || args.contains(&"-h".to_string()) {
@@ -46,11 +248,41 @@ fn main() -> io::Result<()> {
         return Ok(());
     }
 
-    let device = if let Some(dev) = device {
-        dev
+    if args.len() > 1 && args[1] == "free-space" {
+        return run_free_space_wipe(args.get(2), force);
+    }
+
+    if args.len() > 1 && args[1] == "queue" {
+        return run_queue_command(&args, force);
+    }
+
+    if args.len() > 1 && args[1] == "schema" {
+        print!("{}", certificate::SCHEMA);
+        return Ok(());
+    }
+
+    if args.len() > 1 && args[1] == "verify-cert" {
+        let mut trusted: Vec<String> = option_value(&args, "--expect-fingerprint").into_iter().collect();
+        if let Some(path) = option_value(&args, "--trusted-keys") {
+            trusted.extend(signing::read_trusted_keys(&path)?);
+        }
+        return run_verify_cert(&positional_args(&args[2..]), &trusted);
+    }
+
+    if args.len() > 1 && args[1] == "audit" {
+        let mode = options.residue_scan.unwrap_or(residue::ScanMode::Sampled);
+        return run_audit(&positional_args(&args[2..]), options.verify_coverage, mode);
+    }
+
+    if args.len() > 1 && args[1] == "shred" {
+        return run_shred(&positional_args(&args[2..]), force);
+    }
+
+    let devices = if !devices.is_empty() {
+        devices
     } else {
         // Interactive device selection
-        select_device_interactively()?
+        select_devices_interactively()?
     };
 
     // Display banner
@@ -59,32 +291,382 @@ fn main() -> io::Result<()> {
     // List available devices
     list_block_devices()?;
 
-    // Validate device
-    if !Path::new(&device).exists() {
-        eprintln!("❌ Error: Device '{}' does not exist!", device);
+    // Validate devices
+    for device in &devices {
+        if !Path::new(device).exists() {
+            eprintln!("❌ Error: Device '{}' does not exist!", device);
+            return Ok(());
+        }
+    }
+
+    let devices = match parallel::distinct_devices(&devices) {
+        Ok(devices) => devices,
+        Err(e) => {
+            eprintln!("❌ Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Certificates must be signable before any data is destroyed
+    if let Err(e) = signing::StationKey::load_or_create(&options.station_key) {
+        eprintln!("❌ Error: Cannot use station key {}: {}", options.station_key, e);
         return Ok(());
     }
 
     // Safety confirmation
-    if !force && !confirm_wipe(&device)? {
+    if !force && !confirm_wipe(&devices)? {
         println!("🛑 Wipe operation cancelled by user.");
         return Ok(());
     }
 
+    if devices.len() > 1 {
+        let results = parallel::run_parallel_wipes(&devices, &forwarded_options(&args))?;
+        parallel::print_summary(&results);
+        if results.iter().any(|r| !r.success) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let device = &devices[0];
+
     // Perform LUKS crypto wipe
-    match perform_luks_crypto_wipe(&device, verify) {
-        Ok(_) => {
+    match perform_crypto_wipe(device, &options) {
+        Ok(defects) if defects.is_empty() => {
             println!("\n✅ LUKS crypto wipe completed successfully!");
             println!("🔒 Device '{}' has been securely wiped using LUKS encryption.", device);
         }
+        Ok(defects) => {
+            println!("\n⚠️  LUKS crypto wipe completed with {} unwritable ranges!", defects.len());
+            println!("🔨 Data in those sectors may survive - route '{}' to physical destruction.", device);
+            std::process::exit(2);
+        }
         Err(e) => {
             eprintln!("❌ Wipe failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+// Options that take a value, so the value is not mistaken for a device
+const VALUE_OPTIONS: &[&str] = &[
+    "--provision", "--layout", "--label", "--checkpoint-dir", "--max-rate", "--bus-rate", "--range",
+    "--verify-coverage", "--residue-scan", "--method", "--cipher", "--key-size", "--hash", "--pbkdf",
+    "--certificate-dir", "--operator", "--station-key", "--expect-fingerprint", "--trusted-keys",
+    "--validator", "--media-source", "--confidentiality", "--destination",
+    "--priority", "--concurrency", "--queue-dir",
+];
+
+// Queue options that configure the queue itself rather than the wipe job
+const QUEUE_OPTIONS: &[&str] = &["--priority", "--concurrency", "--queue-dir", "--drain"];
+
+/// Arguments that are neither options nor option values.
+fn positional_args(args: &[String]) -> Vec<String> {
+    let mut positional = Vec::new();
+    let mut skip_value = false;
+    for arg in args {
+        if skip_value {
+            skip_value = false;
+        } else if VALUE_OPTIONS.contains(&arg.as_str()) {
+            skip_value = true;
+        } else if !arg.starts_with('-') {
+            positional.push(arg.clone());
+        }
+    }
+    positional
+}
+
+/// Options (with their values) to hand on to per-device wipe jobs.
+fn forwarded_options(args: &[String]) -> Vec<String> {
+    let mut forwarded = Vec::new();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if VALUE_OPTIONS.contains(&arg.as_str()) {
+            forwarded.push(arg.clone());
+            forwarded.extend(iter.next().cloned());
+        } else if arg.starts_with('-') && arg != "--force" && arg != "-f" {
+            forwarded.push(arg.clone());
+        }
+    }
+    forwarded
+}
+
+/// Value following a `--name VALUE` style option, if present.
+fn option_value(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+/// Parse a MB/s limit option.
+fn rate_option(args: &[String], name: &str) -> io::Result<Option<u64>> {
+    match option_value(args, name) {
+        Some(value) => match value.parse::<u64>() {
+            Ok(rate) if rate > 0 => Ok(Some(rate)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} needs a positive number of MB/s", name)
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+fn run_queue_command(args: &[String], force: bool) -> io::Result<()> {
+    let queue_dir = option_value(args, "--queue-dir")
+        .unwrap_or_else(|| queue::DEFAULT_QUEUE_DIR.to_string());
+    let job_queue = queue::JobQueue::open(&queue_dir)?;
+    let positional = positional_args(&args[2..]);
+
+    match positional.first().map(String::as_str) {
+        Some("add") => {
+            let devices = &positional[1..];
+            if devices.is_empty() {
+                eprintln!("❌ Error: queue add needs at least one device");
+                return Ok(());
+            }
+            for device in devices {
+                if !Path::new(device).exists() {
+                    eprintln!("❌ Error: Device '{}' does not exist!", device);
+                    return Ok(());
+                }
+            }
+            // Jobs run concurrently, so no two may share media
+            let devices = &parallel::distinct_devices(devices)?;
+            for device in devices {
+                job_queue.check_overlap(device)?;
+            }
+            if !force && !confirm_wipe(devices)? {
+                println!("🛑 Submission cancelled by user.");
+                return Ok(());
+            }
+
+            let priority = match option_value(args, "--priority") {
+                Some(value) => value.parse().map_err(|_| io::Error::new(
+                    io::ErrorKind::InvalidInput, "Priority must be a whole number"
+                ))?,
+                None => 0,
+            };
+            let job_options = strip_queue_options(&forwarded_options(args));
+            let method = match option_value(args, "--method") {
+                Some(value) => WipeMethod::parse(&value)?,
+                None => WipeMethod::Luks2,
+            };
+
+            for device in devices {
+                let job = job_queue.submit(device, method.name(), priority, &job_options)?;
+                println!("📥 Queued job {} for {} ({} / {})",
+                    job.job_id, device, job.identity.model, job.identity.serial);
+            }
+        }
+        Some("list") | None => queue::print_queue(&job_queue)?,
+        Some("run") => {
+            let concurrency = match option_value(args, "--concurrency") {
+                Some(value) => value.parse().map_err(|_| io::Error::new(
+                    io::ErrorKind::InvalidInput, "Concurrency must be a positive number"
+                ))?,
+                None => queue::DEFAULT_CONCURRENCY,
+            };
+            queue::run_scheduler(&job_queue, concurrency, args.contains(&"--drain".to_string()))?;
+        }
+        Some("remove") => match positional.get(1) {
+            Some(job_id) => {
+                job_queue.remove(job_id)?;
+                println!("🗑️  Removed job {}", job_id);
+            }
+            None => eprintln!("❌ Error: queue remove needs a job ID"),
+        },
+        Some(other) => eprintln!("❌ Error: unknown queue command '{}'", other),
+    }
+
+    Ok(())
+}
+
+/// Drop queue-level options (and their values) from a job's option list.
+fn strip_queue_options(options: &[String]) -> Vec<String> {
+    let mut kept = Vec::new();
+    let mut iter = options.iter();
+    while let Some(option) = iter.next() {
+        if QUEUE_OPTIONS.contains(&option.as_str()) {
+            if VALUE_OPTIONS.contains(&option.as_str()) {
+                iter.next();
+            }
+        } else {
+            kept.push(option.clone());
+        }
+    }
+    kept
+}
+
+fn run_audit(devices: &[String], coverage_percent: f64, mode: residue::ScanMode) -> io::Result<()> {
+    if devices.is_empty() {
+        eprintln!("❌ Error: audit needs at least one device");
+        std::process::exit(1);
+    }
+
+    let mut all_passed = true;
+    for device in devices {
+        if !Path::new(device).exists() {
+            eprintln!("❌ Error: Device '{}' does not exist!", device);
+            std::process::exit(1);
+        }
+        let record = audit::audit_device(device, coverage_percent, mode, rand::random())?;
+        audit::print_audit_report(&record);
+        all_passed &= record.passed();
+    }
+
+    if !all_passed {
+        std::process::exit(2);
+    }
+    Ok(())
+}
+
+fn run_verify_cert(files: &[String], trusted: &[String]) -> io::Result<()> {
+    if files.is_empty() {
+        eprintln!("❌ Error: verify-cert needs at least one certificate file");
+        std::process::exit(1);
+    }
+
+    let mut all_valid = true;
+    for file in files {
+        let checked = fs::read_to_string(file)
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Not a JSON certificate: {}", e))))
+            .and_then(|document| signing::verify(&document).map(|verified| (document, verified)));
+
+        match checked {
+            // The embedded key proves nothing by itself: an edited certificate
+            // re-signed with a fresh key verifies against that key just as well
+            Ok((_, verified)) if trusted.is_empty() => {
+                println!("⚠️  {}: signature intact, but the signing station {} is UNVERIFIED", file, verified.key_fingerprint);
+                println!("   Anyone can re-sign an edited certificate with a new key; pass the station's");
+                println!("   published fingerprint with --expect-fingerprint or --trusted-keys");
+                all_valid = false;
+            }
+            Ok((_, verified)) if !trusted.contains(&verified.key_fingerprint) => {
+                println!("❌ {}: signature valid, but signed by {} - not a trusted station", file, verified.key_fingerprint);
+                all_valid = false;
+            }
+            Ok((document, verified)) => {
+                let field = |pointer: &str| document.pointer(pointer).and_then(|v| v.as_str()).unwrap_or("?").to_string();
+                println!("✅ {}: signature valid, signed by a trusted station", file);
+                println!("   Operation: {}", field("/operation_id"));
+                println!("   Drive: {} (serial {})", field("/device/model"), field("/device/serial"));
+                println!("   Method: {}, result: {}", field("/method/name"), field("/result/status"));
+                println!("   Signed by: {}", verified.key_fingerprint);
+            }
+            Err(e) => {
+                println!("❌ {}: {}", file, e);
+                all_valid = false;
+            }
+        }
+    }
+
+    if !all_valid {
+        std::process::exit(2);
+    }
+    Ok(())
+}
+
+fn run_free_space_wipe(target: Option<&String>, force: bool) -> io::Result<()> {
+    let target = match target {
+        Some(target) if !target.starts_with('-') => target,
+        _ => {
+            eprintln!("❌ Error: free-space needs a mount point or mounted device");
+            return Ok(());
+        }
+    };
+
+    let mount_point = free_space::resolve_mount_point(target)?;
+    if !force && !confirm_free_space_wipe(&mount_point)? {
+        println!("🛑 Free-space wipe cancelled by user.");
+        return Ok(());
+    }
+
+    match free_space::wipe_free_space(&mount_point) {
+        Ok(report) => {
+            free_space::print_free_space_report(&report);
+            println!("\n✅ Free-space wipe completed successfully!");
+        }
+        Err(e) => {
+            eprintln!("❌ Free-space wipe failed: {}", e);
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+fn confirm_free_space_wipe(mount_point: &str) -> io::Result<bool> {
+    println!("\x1b[33m");  // Yellow color
+    println!("⚠️  FREE-SPACE WIPE ⚠️");
+    println!("═══════════════════════");
+    println!("Filesystem: {}", mount_point);
+    println!("This will:");
+    println!("  📝 Fill all free space with random data");
+    println!("  🗑️  Delete the fill file and discard freed blocks");
+    println!("  📂 Keep all existing files");
+    println!("The filesystem will be completely full for a while - stop other writers first.");
+    println!();
+    println!("\x1b[0m");   // Reset color
+
+    print!("Type 'WIPE FREE SPACE' to confirm: ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(input.trim() == "WIPE FREE SPACE")
+}
+
+fn run_shred(paths: &[String], force: bool) -> io::Result<()> {
+    if paths.is_empty() {
+        eprintln!("❌ Error: shred needs at least one file or directory");
+        return Ok(());
+    }
+
+    if !force && !confirm_shred(paths)? {
+        println!("🛑 Shred cancelled by user.");
+        return Ok(());
+    }
+
+    match shred::shred_paths(paths) {
+        Ok(report) => {
+            shred::print_shred_report(&report);
+            println!("\n✅ Shred completed successfully!");
+        }
+        Err(e) => {
+            eprintln!("❌ Shred failed: {}", e);
+            return Err(e);
         }
     }
 
     Ok(())
 }
 
+fn confirm_shred(paths: &[String]) -> io::Result<bool> {
+    println!("\x1b[33m");  // Yellow color
+    println!("⚠️  SECURE FILE DELETION ⚠️");
+    println!("═══════════════════════════");
+    println!("The following will be PERMANENTLY destroyed:");
+    for path in paths {
+        println!("  🗑️  {}", path);
+    }
+    println!("Directories are removed with everything inside them.");
+    println!();
+    println!("\x1b[0m");   // Reset color
+
+    print!("Type 'SHRED' to confirm: ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(input.trim() == "SHRED")
+}
+
 fn display_banner() {
     println!("\x1b[31m");  // Red color
     println!("
@@ -134,7 +716,7 @@ fn auto_unmount_device(device_path: &str) -> io::Result<()> {
     
     // Check if the specific device is mounted
     let mount_output = ProcessCommand::new("findmnt")
-        .args(&["-n", "-o", "TARGET", device_path])
+        .args(["-n", "-o", "TARGET", device_path])
         .output()?;
     
     if mount_output.status.success() && !mount_output.stdout.is_empty() {
@@ -148,8 +730,7 @@ fn auto_unmount_device(device_path: &str) -> io::Result<()> {
         if umount_result.success() {
             println!("✅ Successfully unmounted {}", device_path);
         } else {
-            return Err(io::Error::new(
-                io::ErrorKind::Other, 
+            return Err(io::Error::other(
                 format!("Failed to unmount {} - device may be busy", device_path)
             ));
         }
@@ -162,7 +743,7 @@ fn auto_unmount_device(device_path: &str) -> io::Result<()> {
         println!("🔍 Checking for mounted partitions on {}...", device_path);
         
         let lsblk_output = ProcessCommand::new("lsblk")
-            .args(&["-n", "-o", "NAME,MOUNTPOINT", device_path])
+            .args(["-n", "-o", "NAME,MOUNTPOINT", device_path])
             .output()?;
             
         if lsblk_output.status.success() {
@@ -196,14 +777,13 @@ fn auto_unmount_device(device_path: &str) -> io::Result<()> {
                             println!("⚠️ Failed to unmount {} - trying force unmount...", part_device);
                             
                             let force_umount = ProcessCommand::new("umount")
-                                .args(&["-f", &part_device])
+                                .args(["-f", &part_device])
                                 .status()?;
                                 
                             if force_umount.success() {
                                 println!("✅ Force unmounted {}", part_device);
                             } else {
-                                return Err(io::Error::new(
-                                    io::ErrorKind::Other, 
+                                return Err(io::Error::other(
                                     format!("Failed to unmount partition {} - device busy", part_device)
                                 ));
                             }
@@ -226,7 +806,7 @@ fn list_block_devices() -> io::Result<()> {
     println!("═══════════════════════════");
 
     let output = ProcessCommand::new("lsblk")
-        .args(&["-d", "-o", "NAME,SIZE,TYPE,MODEL"])
+        .args(["-d", "-o", "NAME,SIZE,TYPE,LOG-SEC,PHY-SEC,MIN-IO,OPT-IO,ALIGNMENT,MODEL"])
         .output()?;
 
     if output.status.success() {
@@ -239,24 +819,24 @@ fn list_block_devices() -> io::Result<()> {
     Ok(())
 }
 
-fn select_device_interactively() -> io::Result<String> {
+fn select_devices_interactively() -> io::Result<Vec<String>> {
     println!("\n🎯 STORAGE DEVICE & PARTITION SELECTION");
     println!("═══════════════════════════════════════");
 
     // Get comprehensive list of all block devices and partitions
     let output = ProcessCommand::new("lsblk")
-        .args(&["-n", "-o", "NAME,SIZE,TYPE,MOUNTPOINT,MODEL", "--tree"])
+        .args(["-n", "-o", "NAME,SIZE,TYPE,MOUNTPOINT,MODEL", "--tree"])
         .output()?;
 
     if !output.status.success() {
-        return Err(io::Error::new(io::ErrorKind::Other, "Failed to get device list"));
+        return Err(io::Error::other("Failed to get device list"));
     }
 
     let devices_output = String::from_utf8_lossy(&output.stdout);
     let lines: Vec<&str> = devices_output.lines().collect();
 
     if lines.is_empty() {
-        return Err(io::Error::new(io::ErrorKind::Other, "No storage devices found"));
+        return Err(io::Error::other("No storage devices found"));
     }
 
     // Parse and categorize devices
@@ -287,13 +867,14 @@ fn select_device_interactively() -> io::Result<String> {
             model,
             is_partition: device_type == "part",
             is_removable,
+            geometry: geometry::read_geometry(&format!("/dev/{}", name)).ok(),
         };
 
         devices.push(device_info);
     }
 
     if devices.is_empty() {
-        return Err(io::Error::new(io::ErrorKind::Other, "No suitable devices found"));
+        return Err(io::Error::other("No suitable devices found"));
     }
 
     // Display categorized list
@@ -327,12 +908,17 @@ fn select_device_interactively() -> io::Result<String> {
             ""
         };
 
-        println!("  {}: {} {} - {} {} - {}{}{}",
+        let sectors = device.geometry
+            .map(|geometry| format!(" [{}]", geometry.format_type()))
+            .unwrap_or_default();
+
+        println!("  {}: {} {} - {} {}{} - {}{}{}",
             i + 1,
             icon,
             device.path,
             device.size,
             device_type_display,
+            sectors,
             device.model,
             mount_info,
             warning
@@ -340,40 +926,139 @@ fn select_device_interactively() -> io::Result<String> {
     }
 
     println!("\n💡 Tip: You can wipe entire drives or individual partitions");
+    println!("💡 Tip: Separate several numbers with commas to wipe them in parallel (e.g. 2,3,5)");
     println!("⚠️  WARNING: Selected device/partition will be COMPLETELY DESTROYED!");
-    print!("\nSelect device/partition number (1-{}): ", devices.len());
+    print!("\nSelect device/partition number(s) (1-{}): ", devices.len());
     io::stdout().flush()?;
 
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
 
-    let choice: usize = input.trim().parse()
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Invalid number"))?;
+    let mut selected = Vec::new();
+    for part in input.trim().split(',') {
+        let choice: usize = part.trim().parse()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Invalid number"))?;
 
-    if choice == 0 || choice > devices.len() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid selection"));
+        if choice == 0 || choice > devices.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid selection"));
+        }
+        if !selected.contains(&choice) {
+            selected.push(choice);
+        }
     }
 
-    let selected = &devices[choice - 1];
-    
-    // Additional warning for mounted devices
-    if !selected.mountpoint.is_empty() && selected.mountpoint != "-" {
-        println!("\n⚠️  CRITICAL WARNING ⚠️");
-        println!("The selected device is CURRENTLY MOUNTED at: {}", selected.mountpoint);
-        println!("Wiping it will crash the system if it contains important files!");
-        print!("Type 'I UNDERSTAND THE RISK' to continue: ");
-        io::stdout().flush()?;
-        
-        let mut risk_input = String::new();
-        io::stdin().read_line(&mut risk_input)?;
-        
-        if risk_input.trim() != "I UNDERSTAND THE RISK" {
-            return Err(io::Error::new(io::ErrorKind::Other, "Operation cancelled for safety"));
+    let mut paths = Vec::new();
+    for choice in selected {
+        let selected = &devices[choice - 1];
+
+        // Additional warning for mounted devices
+        if !selected.mountpoint.is_empty() && selected.mountpoint != "-" {
+            println!("\n⚠️  CRITICAL WARNING ⚠️");
+            println!("{} is CURRENTLY MOUNTED at: {}", selected.path, selected.mountpoint);
+            println!("Wiping it will crash the system if it contains important files!");
+            print!("Type 'I UNDERSTAND THE RISK' to continue: ");
+            io::stdout().flush()?;
+
+            let mut risk_input = String::new();
+            io::stdin().read_line(&mut risk_input)?;
+
+            if risk_input.trim() != "I UNDERSTAND THE RISK" {
+                return Err(io::Error::other("Operation cancelled for safety"));
+            }
+        }
+
+        println!("✅ Selected: {} ({} {})", selected.path, selected.size, selected.device_type);
+        paths.push(selected.path.clone());
+    }
+
+    Ok(paths)
+}
+
+struct WipeOptions {
+    method: WipeMethod,
+    verify: bool,
+    verify_coverage: f64,
+    verify_full: bool,
+    residue_scan: Option<residue::ScanMode>,
+    provision: Option<ProvisionSpec>,
+    luks: LuksParams,
+    auto_cipher: bool,
+    resume: bool,
+    checkpoint_dir: String,
+    max_rate: Option<u64>,
+    bus_rate: Option<u64>,
+    range: Option<String>,
+    certificate_dir: String,
+    disposition: Disposition,
+    station_key: String,
+}
+
+// Size of the LUKS2 header area zeroed by destroy_luks_header
+const LUKS_HEADER_WIPE: u64 = 16 * 1024 * 1024;
+
+// Smallest extent a LUKS2 header (16 MiB) plus a useful data area fits in
+const MIN_LUKS_RANGE: u64 = 32 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WipeMethod {
+    /// LUKS2 header with a random key, overwritten afterwards
+    Luks2,
+    /// LUKS2 with its header in a tmpfs file, never written to the target
+    DetachedHeader,
+    /// Plain dm-crypt with a random key piped to cryptsetup, no header
+    Plain,
+    /// Single pass of 0x00 written directly to the device
+    Zeros,
+    /// Single pass of 0xFF written directly to the device
+    Ones,
+    /// Single pass of a ChaCha20 stream from a random seed
+    Random,
+}
+
+impl WipeMethod {
+    fn parse(value: &str) -> io::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "luks" | "luks2" => Ok(WipeMethod::Luks2),
+            "detached" => Ok(WipeMethod::DetachedHeader),
+            "plain" => Ok(WipeMethod::Plain),
+            "zero" | "zeros" => Ok(WipeMethod::Zeros),
+            "ones" => Ok(WipeMethod::Ones),
+            "random" => Ok(WipeMethod::Random),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown method '{}' (expected luks, detached, plain, zero, ones or random)", other)
+            )),
         }
     }
 
-    println!("✅ Selected: {} ({} {})", selected.path, selected.size, selected.device_type);
-    Ok(selected.path.clone())
+    /// Name recorded in checkpoints, queue jobs and reports.
+    fn name(&self) -> &'static str {
+        match self {
+            WipeMethod::Luks2 => "luks2-crypto-fill",
+            WipeMethod::DetachedHeader => "luks2-detached-crypto-fill",
+            WipeMethod::Plain => "plain-crypto-fill",
+            WipeMethod::Zeros => "overwrite-zeros",
+            WipeMethod::Ones => "overwrite-ones",
+            WipeMethod::Random => "overwrite-random",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            WipeMethod::Luks2 => "LUKS crypto",
+            WipeMethod::DetachedHeader => "detached-header LUKS crypto",
+            WipeMethod::Plain => "plain dm-crypt crypto",
+            WipeMethod::Zeros => "zero overwrite",
+            WipeMethod::Ones => "0xFF overwrite",
+            WipeMethod::Random => "random overwrite",
+        }
+    }
+
+    /// Whether the method writes a known pattern straight to the device
+    /// rather than filling it through an encrypted mapping.
+    fn is_overwrite(&self) -> bool {
+        matches!(self, WipeMethod::Zeros | WipeMethod::Ones | WipeMethod::Random)
+    }
 }
 
 #[derive(Debug)]
@@ -385,13 +1070,14 @@ struct DeviceInfo {
     model: String,
     is_partition: bool,
     is_removable: bool,
+    geometry: Option<SectorGeometry>,
 }
 
-fn confirm_wipe(device: &str) -> io::Result<bool> {
+fn confirm_wipe(devices: &[String]) -> io::Result<bool> {
     println!("\x1b[33m");  // Yellow color
     println!("⚠️  DANGER ZONE ⚠️");
     println!("═══════════════════");
-    println!("You are about to PERMANENTLY WIPE: {}", device);
+    println!("You are about to PERMANENTLY WIPE: {}", devices.join(", "));
     println!("This will:");
     println!("  🔥 Destroy ALL data on the device");
     println!("  🔐 Create LUKS encryption");
@@ -409,82 +1095,448 @@ fn confirm_wipe(device: &str) -> io::Result<bool> {
     Ok(input.trim() == "DESTROY ALL DATA")
 }
 
-fn perform_luks_crypto_wipe(device: &str, verify: bool) -> io::Result<()> {
-    let wipe_id = Uuid::new_v4();
-    println!("🚀 Starting LUKS crypto wipe...");
+/// Returns the byte ranges of the device that could not be overwritten.
+fn perform_crypto_wipe(device: &str, options: &WipeOptions) -> io::Result<Vec<ByteRange>> {
+    let identity = identity::read_identity(device);
+    let geometry = geometry::read_geometry(device).unwrap_or_else(|e| {
+        println!("⚠️  Could not read sector geometry of {} ({}) - assuming 512-byte sectors", device, e);
+        SectorGeometry::default()
+    });
+    let range = match &options.range {
+        Some(value) => {
+            let range = ByteRange::parse(value, geometry.logical, range::device_size(device)?)?;
+            if !geometry.is_physically_aligned(range.start) || !geometry.is_physically_aligned(range.end) {
+                println!("⚠️  Range is not aligned to the {}-byte physical sectors - edge sectors will be read-modify-written",
+                    geometry.physical);
+            }
+            if options.method == WipeMethod::Luks2 && range.len() < MIN_LUKS_RANGE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Range is too small for a LUKS crypto wipe (minimum {} bytes)", MIN_LUKS_RANGE)
+                ));
+            }
+            Some(range)
+        }
+        None => None,
+    };
+    // Checkpoints are keyed by model, serial and size; without a serial two
+    // drives of the same model and size would share one, so none is kept
+    if options.resume && !identity.has_serial() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} reports no serial number, so its checkpoint cannot be told apart from another drive's - --resume refused", device)
+        ));
+    }
+    let store = if identity.has_serial() {
+        CheckpointStore::open(&options.checkpoint_dir, device)?
+    } else {
+        println!("⚠️  {} reports no serial number - progress is not checkpointed and the wipe cannot be resumed", device);
+        CheckpointStore::disabled()
+    };
+    let (wipe_id, mut checkpoint) = match store.load(&identity)? {
+        Some(mut previous) if options.resume && previous.method == options.method.name() && previous.range == range => {
+            println!("♻️  Resuming interrupted wipe at {:.2} GiB", previous.offset as f64 / (1u64 << 30) as f64);
+            previous.resumes.push(ResumeEvent {
+                offset: previous.offset,
+                interrupted_at: previous.updated,
+                resumed_at: checkpoint::unix_now(),
+            });
+            previous.device = device.to_string();
+            let wipe_id = Uuid::parse_str(&previous.operation_id).unwrap_or_else(|_| Uuid::new_v4());
+            (wipe_id, previous)
+        }
+        previous => {
+            if let Some(previous) = previous {
+                println!("⚠️  Found checkpoint of interrupted wipe {} - starting over (use --resume to continue it)",
+                    previous.operation_id);
+            }
+            let wipe_id = Uuid::new_v4();
+            let now = checkpoint::unix_now();
+            (wipe_id, Checkpoint {
+                operation_id: wipe_id.to_string(),
+                device: device.to_string(),
+                identity: identity.clone(),
+                method: options.method.name().to_string(),
+                pass: 1,
+                passes: 1,
+                range,
+                pattern_seed: None,
+                offset: 0,
+                started: now,
+                updated: now,
+                resumes: Vec::new(),
+                defects: Vec::new(),
+            })
+        }
+    };
+
+    println!("🚀 Starting {} wipe...", options.method.title());
     println!("🆔 Operation ID: {}", wipe_id);
     println!("📱 Target: {}", device);
+    println!("💽 Sectors: {}", geometry.describe());
+    if let Some(range) = &range {
+        println!("📏 Range: {}", range.describe(geometry.logical));
+    }
     println!();
 
-    // Step 0: Auto-unmount if necessary (especially important for USB devices)
-    println!("🔄 Step 0: Preparing device...");
-    auto_unmount_device(device)?;
-    
-    // Add a small delay for USB devices to settle
-    let device_name = device.strip_prefix("/dev/").unwrap_or(device);
-    if is_removable_device(device_name) {
-        println!("🔌 USB/Removable device detected, allowing time to settle...");
-        thread::sleep(Duration::from_secs(2));
-    }
-    println!("✅ Device ready for wiping");
+    let mut record = WipeRecord::new(&wipe_id.to_string(), device, &identity, options.method.name(), geometry);
+    record.range = range;
+    record.resumes = checkpoint.resumes.clone();
+    record.media = identity::read_media_info(device);
+    record.disposition = options.disposition.clone();
+
+    // Every step runs through the record; on the first error the report is
+    // still printed, showing exactly which step failed and which never ran
+    let outcome = (|| -> io::Result<()> {
+        // Step 0: Auto-unmount if necessary (especially important for USB devices)
+        println!("🔄 Step 0: Preparing device...");
+        let mapping = record.run_step("Prepare device", || {
+            auto_unmount_device(device)?;
+
+            // Add a small delay for USB devices to settle
+            let device_name = device.strip_prefix("/dev/").unwrap_or(device);
+            if is_removable_device(device_name) {
+                println!("🔌 USB/Removable device detected, allowing time to settle...");
+                thread::sleep(Duration::from_secs(2));
+            }
 
-    // Step 1: Generate random passphrase
-    println!("\n🔑 Step 1: Generating cryptographic key...");
-    let passphrase = generate_random_passphrase();
-    println!("✅ Cryptographic key generated");
+            // A range is exposed as its own block device; every later step runs on
+            // that instead of the whole device
+            match &range {
+                Some(range) => {
+                    let mapping = RangeMapping::create(device, range, &format!("wiperange_{}", wipe_id.simple()))?;
+                    println!("📏 Range mapped as {}", mapping.path());
+                    Ok(Some(mapping))
+                }
+                None => Ok(None),
+            }
+        })?;
+        let target = mapping.as_ref().map(RangeMapping::path).unwrap_or_else(|| device.to_string());
+        record.detail("Target", &target);
+        println!("✅ Device ready for wiping");
+
+        // Keys and patterns may only come from a ready, healthy random source;
+        // a live USB straight after boot can still be short of entropy
+        println!("\n🎲 Checking the kernel random source...");
+        let mut entropy = record.run_step("Entropy self-test", entropy::EntropySource::start)?;
+        record.entropy = Some(entropy.status());
+        println!("✅ {}", entropy.status().describe());
+
+        // Overwrite methods write their pattern straight to the target. The
+        // stream seed is kept in the checkpoint so a resumed pass continues
+        // the same stream and the read-back can regenerate it
+        let pattern = match options.method {
+            WipeMethod::Zeros => Some(Pattern::Fill(0x00)),
+            WipeMethod::Ones => Some(Pattern::Fill(0xff)),
+            WipeMethod::Random => {
+                if checkpoint.pattern_seed.is_none() {
+                    let mut seed = [0u8; 32];
+                    entropy.fill(&mut seed)?;
+                    checkpoint.pattern_seed = Some(seed);
+                }
+                checkpoint.pattern_seed.map(Pattern::Stream)
+            }
+            WipeMethod::Luks2 | WipeMethod::DetachedHeader | WipeMethod::Plain => None,
+        };
+        record.pattern = pattern;
 
-    // Step 2: Create LUKS partition
-    println!("\n🔐 Step 2: Setting up LUKS encryption...");
-    create_luks_partition(device, &passphrase)?;
-    println!("✅ LUKS partition created");
+        let params = if options.auto_cipher && pattern.is_none() {
+            let params = record.run_step("Benchmark ciphers", || cipher::choose_fastest(&options.luks))?;
+            record.detail("Chosen", format!("{} ({} bits)", params.cipher, params.key_size));
+            params
+        } else {
+            options.luks.clone()
+        };
+        let mapper_name = format!("cryptowipe_{}", wipe_id.simple());
+
+        // The detached header lives in RAM for the whole wipe and is
+        // discarded in step 5 (or on drop if a step fails)
+        let detached = match options.method {
+            WipeMethod::DetachedHeader => Some(record.run_step("Reserve RAM header file", || DetachedHeader::create(&mapper_name))?),
+            _ => None,
+        };
+        let header_path = detached.as_ref().map(DetachedHeader::path);
+
+        let encryption = match options.method {
+            WipeMethod::Luks2 | WipeMethod::DetachedHeader => Some({
+                // Step 1: Generate random key material
+                println!("\n🔑 Step 1: Generating cryptographic key...");
+                let passphrase = record.run_step("Generate key", || SecretKey::generate(secret::KEY_BYTES, &mut entropy))?;
+                record.entropy = Some(entropy.status());
+                record.detail("Key material", format!("{} bytes from getrandom, {}", passphrase.len(),
+                    if passphrase.is_locked() { "locked in RAM" } else { "NOT locked in RAM" }));
+                println!("✅ Cryptographic key generated");
+
+                // Step 2: Create LUKS partition
+                println!("\n🔐 Step 2: Setting up LUKS encryption...");
+                let step = if header_path.is_some() { "Create detached LUKS2 header in RAM" } else { "Create LUKS2 header" };
+                let header = record.run_step(step, || {
+                    create_luks_partition(&target, &passphrase, &params, header_path.as_deref())?;
+                    cipher::read_header(header_path.as_deref().unwrap_or(&target))
+                })?;
+                if let Some(path) = &header_path {
+                    record.detail("Header file", path);
+                }
+                record.detail("Parameters", header.describe());
+                // The passphrase above only unlocks the key slot; the volume key
+                // that encrypts the fill comes from cryptsetup itself
+                record.detail("Volume key", "generated by cryptsetup from /dev/random (--use-random), not covered by the entropy health tests");
+                println!("✅ LUKS partition created: {}", header.describe());
+
+                // Step 3: Open LUKS partition
+                println!("\n🔓 Step 3: Opening encrypted partition...");
+                record.run_step("Open encrypted mapping", || {
+                    open_luks_partition(&target, &mapper_name, &passphrase, header_path.as_deref())
+                })?;
+                header
+            }),
+            WipeMethod::Plain => {
+                // Steps 1-3: the key goes from the tested source through a pipe
+                // into dm-crypt; nothing resembling a key slot is ever written
+                println!("\n🔐 Steps 1-3: Opening plain dm-crypt mapping with a random key...");
+                let key = record.run_step("Generate key", || SecretKey::generate(params.key_size as usize / 8, &mut entropy))?;
+                record.entropy = Some(entropy.status());
+                record.detail("Key material", format!("{} bytes from getrandom, {}", key.len(),
+                    if key.is_locked() { "locked in RAM" } else { "NOT locked in RAM" }));
+                Some(record.run_step("Open encrypted mapping", || {
+                    open_plain_mapping(&target, &mapper_name, &params, &key)?;
+                    cipher::read_mapping(&mapper_name)
+                })?)
+            }
+            // Steps 1-3 do not apply: there is no key to generate or destroy
+            WipeMethod::Zeros | WipeMethod::Ones | WipeMethod::Random => None,
+        };
+        if let Some(encryption) = encryption {
+            record.detail("Mapping", format!("/dev/mapper/{}", mapper_name));
+            record.detail("Parameters", encryption.describe());
+            println!("✅ Encrypted mapping opened as /dev/mapper/{}: {}", mapper_name, encryption.describe());
+            record.encryption = Some(encryption);
+        }
 
-    // Step 3: Open LUKS partition
-    println!("\n🔓 Step 3: Opening encrypted partition...");
-    let mapper_name = format!("cryptowipe_{}", wipe_id.simple());
-    open_luks_partition(device, &mapper_name, &passphrase)?;
-    println!("✅ Encrypted partition opened as /dev/mapper/{}", mapper_name);
+        // Step 4: Fill with random data, or write the overwrite pattern
+        // A resumed crypto job runs under a fresh key: everything written before the
+        // interruption is ciphertext whose key only ever existed in RAM
+        let (fill_step, fill_target) = match &pattern {
+            Some(pattern) => {
+                println!("\n📝 Step 4: Overwriting with {}...", pattern.describe());
+                (report::STEP_OVERWRITE, target.clone())
+            }
+            None => {
+                println!("\n📝 Step 4: Filling with encrypted data...");
+                (report::STEP_FILL, format!("/dev/mapper/{}", mapper_name))
+            }
+        };
+        let start = checkpoint.offset;
+        let mut defects = std::mem::take(&mut checkpoint.defects);
+        let filled = record.run_step(fill_step, || {
+            store.save(&checkpoint)?;
+            let mut throttle = Throttle::new(device, options.max_rate, options.bus_rate);
+            let device_offset = range.map_or(0, |r| r.start) + match pattern {
+                Some(_) => 0,
+                None => mapper_data_offset(&mapper_name)?,
+            };
+            // Zeros written through dm-crypt land on disk as random-looking ciphertext
+            let total = write_engine::fill_pattern(
+                &fill_target,
+                start,
+                device_offset,
+                &pattern.unwrap_or(Pattern::Fill(0)),
+                &mut throttle,
+                &mut defects,
+                |offset, defects| {
+                    checkpoint.offset = offset;
+                    checkpoint.defects = defects.to_vec();
+                    checkpoint.updated = checkpoint::unix_now();
+                    store.save(&checkpoint)
+                },
+            )?;
+            // An overwrite is done once the pattern is down; a crypto fill
+            // only once its key is gone
+            if pattern.is_some() {
+                store.remove(&identity)?;
+            }
+            Ok(total)
+        });
+        checkpoint.defects = defects;
+        record.defects = checkpoint.defects.clone();
+        let total = filled?;
+        record.bytes_written = total - start;
+        record.detail("Resumed from offset", start);
+        record.detail("Bytes written", total - start);
+        record.detail("Unwritable ranges", checkpoint.defects.len());
+
+        if pattern.is_some() {
+            println!("✅ Device overwritten");
+            record.skip_step(report::STEP_DESTROY_KEYS, "overwrite method, no keys");
+        } else {
+            println!("✅ Device filled with encrypted data");
+
+            // Step 5: Close and destroy keys
+            println!("\n🔒 Step 5: Closing partition and destroying keys...");
+            record.run_step(report::STEP_DESTROY_KEYS, || {
+                close_luks_partition(&mapper_name)?;
+                match &detached {
+                    Some(header) => header.discard()?,
+                    None if options.method == WipeMethod::Luks2 => destroy_luks_header(&target)?,
+                    None => {}
+                }
+                store.remove(&identity)
+            })?;
+            record.detail("Key", match options.method {
+                WipeMethod::Luks2 => "mapping closed, LUKS header overwritten",
+                WipeMethod::DetachedHeader => "mapping closed, RAM-only header file zeroed and removed",
+                _ => "mapping closed, key only ever existed in kernel memory",
+            });
+            println!("✅ Encryption keys destroyed - data is now unrecoverable");
+
+            // A header that survived would let anyone with the key back in
+            if matches!(options.method, WipeMethod::Luks2 | WipeMethod::DetachedHeader) {
+                println!("\n🔍 Checking all LUKS header locations...");
+                record.run_step(report::STEP_HEADER_CHECK, || {
+                    let found = cipher::find_luks_headers(&target)?;
+                    if !found.is_empty() {
+                        return Err(io::Error::other(
+                            format!("LUKS header survived: {}", found.join("; "))
+                        ));
+                    }
+                    Ok(())
+                })?;
+                record.detail("Checked", format!("offset 0, {} LUKS2 secondary header offsets, cryptsetup isLuks",
+                    cipher::LUKS2_SECONDARY_OFFSETS.len()));
+                println!("✅ No LUKS header left on the device");
+            }
+        }
 
-    // Step 4: Fill with random data
-    println!("\n📝 Step 4: Filling with encrypted data...");
-    fill_with_random_data(&format!("/dev/mapper/{}", mapper_name))?;
-    println!("✅ Device filled with encrypted data");
+        // Step 6: Verification (optional)
+        if let (true, Some(pattern)) = (options.verify_full, pattern) {
+            println!("\n🔍 Step 6: Full read-back verification...");
+            let mut outcome = None;
+            let verified = record.run_step("Full verify", || {
+                let device_offset = range.map_or(0, |r| r.start);
+                let skip = target_defects(&checkpoint.defects, device_offset);
+                let result = verify::verify_full(&target, &pattern, &skip, device_offset)?;
+                let passed = result.passed();
+                let summary = result.describe();
+                outcome = Some(result);
+                if !passed {
+                    return Err(io::Error::other(format!("Verification failed: {}", summary)));
+                }
+                Ok(summary)
+            });
+            record.verified = Some(verified.is_ok());
+            record.full_verification = outcome;
+            println!("✅ Every byte read back matches: {}", verified?);
+        } else if options.verify {
+            println!("\n🔍 Step 6: Verification...");
+            let mut outcome = None;
+            let verified = record.run_step("Verify", || {
+                let plan = verification_plan(&target, options, range.as_ref(), &checkpoint.defects)?;
+                let mut seed = [0u8; 8];
+                entropy.fill(&mut seed)?;
+                let result = verify::verify_samples(&target, &plan, u64::from_le_bytes(seed))?;
+                let passed = result.passed();
+                let summary = result.describe();
+                outcome = Some(result);
+                if !passed {
+                    return Err(io::Error::other(format!("Verification failed: {}", summary)));
+                }
+                Ok(summary)
+            });
+            record.verified = Some(verified.is_ok());
+            record.verification = outcome;
+            println!("✅ Wipe verified: {}", verified?);
+        } else {
+            record.skip_step("Verify", "not requested (--verify)");
+        }
 
-    // Step 5: Close and destroy keys
-    println!("\n🔒 Step 5: Closing partition and destroying keys...");
-    close_luks_partition(&mapper_name)?;
-    destroy_luks_header(device)?;
-    println!("✅ Encryption keys destroyed - data is now unrecoverable");
+        // Step 7: Residue scan (optional) - catches a fill that silently
+        // stopped short or left old structures behind
+        match options.residue_scan {
+            Some(mode) => {
+                println!("\n🔎 Step 7: Scanning for leftover file signatures ({})...", mode.name());
+                let mut outcome = None;
+                let scanned = record.run_step("Residue scan", || {
+                    let mut seed = [0u8; 8];
+                    entropy.fill(&mut seed)?;
+                    let mut result = residue::scan(&target, mode, options.verify_coverage,
+                        u64::from_le_bytes(seed), range.map_or(0, |r| r.start))?;
+                    result.hidden_area = residue::hidden_area(device);
+                    if let Some(hpa) = &result.hidden_area {
+                        println!("⚠️  Host protected area present ({}) - it was neither wiped nor scanned", hpa);
+                    }
+                    let clean = result.clean();
+                    let summary = result.describe();
+                    outcome = Some(result);
+                    if !clean {
+                        return Err(io::Error::other(format!("Residue found: {}", summary)));
+                    }
+                    Ok(summary)
+                });
+                record.residue = outcome;
+                println!("✅ No residue found: {}", scanned?);
+            }
+            None => record.skip_step("Residue scan", "not requested (--residue-scan)"),
+        }
 
-    // Step 6: Verification (optional)
-    if verify {
-        println!("\n🔍 Step 6: Verification...");
-        verify_wipe(device)?;
-        println!("✅ Wipe verification completed");
-    }
+        // Step 8: Re-provisioning for resale (optional)
+        match &options.provision {
+            Some(spec) => {
+                println!("\n💿 Step 8: Re-provisioning drive...");
+                let provisioned = record.run_step("Re-provision", || provision::provision_device(device, spec))?;
+                record.detail("Filesystem", provisioned.filesystem.name());
+                record.detail("Partition", &provisioned.partition);
+                println!("✅ Drive provisioned with {} on {}", provisioned.filesystem.name(), provisioned.partition);
+                record.provisioned = Some(provisioned);
+            }
+            None => record.skip_step("Re-provision", "not requested (--provision)"),
+        }
 
-    // Generate report
-    generate_completion_report(device, &wipe_id);
+        drop(mapping);
+        Ok(())
+    })();
 
-    Ok(())
+    if outcome.is_ok() {
+        record.finished = Some(checkpoint::unix_now());
+    }
+    report::print_report(&record);
+    // The certificate is written whatever the outcome, so failures are on file too
+    let written = signing::StationKey::load_or_create(&options.station_key)
+        .and_then(|station| certificate::write(&record, &options.certificate_dir, &station));
+    match &written {
+        Ok(paths) => {
+            for path in paths {
+                println!("📜 Certificate: {}", path.display());
+            }
+        }
+        Err(e) => eprintln!("❌ Could not write certificate to {}: {}", options.certificate_dir, e),
+    }
+    outcome?;
+    written?;
+
+    Ok(record.defects)
 }
 
-fn generate_random_passphrase() -> String {
-    let mut rng = thread_rng();
-    let charset = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789!@#$%^&*";
-    (0..64)
-        .map(|_| {
-            let idx = rng.gen_range(0..charset.len());
-            charset.chars().nth(idx).unwrap()
-        })
-        .collect()
+/// Encrypt in physical-sector units so 512e drives are never written in
+/// partial physical sectors; only possible when the data area divides evenly.
+fn crypt_sector_size(device: &str) -> u64 {
+    geometry::read_geometry(device)
+        .ok()
+        .filter(|g| (512..=4096).contains(&g.physical) && g.physical.is_power_of_two())
+        .filter(|g| range::device_size(device).is_ok_and(|size| size % g.physical == 0))
+        .map(|g| g.physical)
+        .unwrap_or(512)
 }
 
-fn create_luks_partition(device: &str, passphrase: &str) -> io::Result<()> {
+/// With `header` set the LUKS2 header goes to that file and the data area
+/// starts at offset 0 of `device`.
+fn create_luks_partition(device: &str, key: &SecretKey, params: &LuksParams, header: Option<&str>) -> io::Result<()> {
     let device_name = device.strip_prefix("/dev/").unwrap_or(device);
     let is_usb = is_removable_device(device_name);
     
     // For USB devices, use faster iteration time to avoid timeout issues
     let iter_time = if is_usb { "1000" } else { "2000" };
+
+    let sector_size = crypt_sector_size(device).to_string();
     
     let mut attempts = 0;
     let max_attempts = if is_usb { 3 } else { 1 };
@@ -496,35 +1548,22 @@ fn create_luks_partition(device: &str, passphrase: &str) -> io::Result<()> {
             thread::sleep(Duration::from_secs(1));
         }
         
-        let mut child = ProcessCommand::new("cryptsetup")
-            .args(&[
-                "luksFormat",
-                "--type", "luks2",
-                "--cipher", "aes-xts-plain64",
-                "--key-size", "512",
-                "--hash", "sha256",
-                "--iter-time", iter_time,
-                "--use-random",
-                device
-            ])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
-
-        if let Some(mut stdin) = child.stdin.take() {
-            writeln!(stdin, "{}", passphrase)?;
-        }
-
-        let output = child.wait_with_output()?;
+        let output = secret::run_with_key(
+            ProcessCommand::new("cryptsetup")
+                .args(["luksFormat", "--type", "luks2"])
+                .args(params.format_args(iter_time))
+                .args(header.map(|path| vec!["--header", path]).unwrap_or_default())
+                .args(["--key-file=-", "--keyfile-size", &key.len().to_string()])
+                .args(["--sector-size", &sector_size, "--use-random", "--batch-mode", device]),
+            key,
+        )?;
         if output.status.success() {
             return Ok(());
         }
         
         let error_msg = String::from_utf8_lossy(&output.stderr);
         if attempts >= max_attempts {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
+            return Err(io::Error::other(
                 format!("Failed to create LUKS partition after {} attempts: {}", attempts, error_msg)
             ));
         }
@@ -536,7 +1575,7 @@ fn create_luks_partition(device: &str, passphrase: &str) -> io::Result<()> {
     }
 }
 
-fn open_luks_partition(device: &str, mapper_name: &str, passphrase: &str) -> io::Result<()> {
+fn open_luks_partition(device: &str, mapper_name: &str, key: &SecretKey, header: Option<&str>) -> io::Result<()> {
     let device_name = device.strip_prefix("/dev/").unwrap_or(device);
     let is_usb = is_removable_device(device_name);
     
@@ -545,22 +1584,16 @@ fn open_luks_partition(device: &str, mapper_name: &str, passphrase: &str) -> io:
         thread::sleep(Duration::from_millis(500)); // Small delay for USB devices
     }
     
-    let mut child = ProcessCommand::new("cryptsetup")
-        .args(&["luksOpen", device, mapper_name])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
-
-    if let Some(mut stdin) = child.stdin.take() {
-        writeln!(stdin, "{}", passphrase)?;
-    }
-
-    let output = child.wait_with_output()?;
+    let output = secret::run_with_key(
+        ProcessCommand::new("cryptsetup")
+            .args(["luksOpen", device, mapper_name])
+            .args(header.map(|path| vec!["--header", path]).unwrap_or_default())
+            .args(["--key-file=-", "--keyfile-size", &key.len().to_string()]),
+        key,
+    )?;
     if !output.status.success() {
         let error_msg = String::from_utf8_lossy(&output.stderr);
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
+        return Err(io::Error::other(
             format!("Failed to open LUKS partition: {}", error_msg)
         ));
     }
@@ -568,29 +1601,58 @@ fn open_luks_partition(device: &str, mapper_name: &str, passphrase: &str) -> io:
     Ok(())
 }
 
-fn fill_with_random_data(mapper_device: &str) -> io::Result<()> {
-    let _output = ProcessCommand::new("dd")
-        .args(&[
-            "if=/dev/zero",
-            &format!("of={}", mapper_device),
-            "bs=8M",
-            "status=progress"
-        ])
+/// Byte offset of the encrypted data area on the device under the mapping,
+/// i.e. where mapper offset 0 lands on disk.
+fn mapper_data_offset(mapper_name: &str) -> io::Result<u64> {
+    let output = ProcessCommand::new("dmsetup")
+        .args(["table", mapper_name])
         .output()?;
 
-    // dd will return non-zero when it hits end of device, which is expected
-    println!("Random data fill completed");
+    // <start> <length> crypt <cipher> <key> <iv_offset> <device> <offset> [<opts>]
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(7)
+        .and_then(|sectors| sectors.parse::<u64>().ok())
+        .map(|sectors| sectors * range::DM_SECTOR)
+        .ok_or_else(|| io::Error::other(
+            format!("Failed to read data offset of {}", mapper_name)
+        ))
+}
+
+fn open_plain_mapping(device: &str, mapper_name: &str, params: &LuksParams, key: &SecretKey) -> io::Result<()> {
+    // A key file is used as-is, without hashing, so dm-crypt gets exactly the
+    // tested bytes
+    let output = secret::run_with_key(
+        ProcessCommand::new("cryptsetup").args([
+            "open",
+            "--type", "plain",
+            "--cipher", &params.cipher,
+            "--key-size", &params.key_size.to_string(),
+            "--key-file=-",
+            "--keyfile-size", &key.len().to_string(),
+            "--sector-size", &crypt_sector_size(device).to_string(),
+            device,
+            mapper_name,
+        ]),
+        key,
+    )?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(
+            format!("Failed to open plain dm-crypt mapping: {}", String::from_utf8_lossy(&output.stderr).trim())
+        ));
+    }
+
     Ok(())
 }
 
 fn close_luks_partition(mapper_name: &str) -> io::Result<()> {
     let output = ProcessCommand::new("cryptsetup")
-        .args(&["luksClose", mapper_name])
+        .args(["luksClose", mapper_name])
         .output()?;
 
     if !output.status.success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
+        return Err(io::Error::other(
             format!("Failed to close LUKS partition: {}", String::from_utf8_lossy(&output.stderr))
         ));
     }
@@ -599,20 +1661,21 @@ fn close_luks_partition(mapper_name: &str) -> io::Result<()> {
 }
 
 fn destroy_luks_header(device: &str) -> io::Result<()> {
-    // Overwrite LUKS header with zeros (sufficient for destruction)
+    // Overwrite LUKS header with zeros (sufficient for destruction);
+    // 2 x 8 MiB = LUKS_HEADER_WIPE
     let output = ProcessCommand::new("dd")
-        .args(&[
+        .args([
             "if=/dev/zero",
             &format!("of={}", device),
             "bs=8M",
             "count=2",
-            "conv=notrunc"
+            // Make sure the zeros are on the media before the header check reads them
+            "conv=notrunc,fsync"
         ])
         .output()?;
 
     if !output.status.success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
+        return Err(io::Error::other(
             "Failed to destroy LUKS header"
         ));
     }
@@ -620,52 +1683,44 @@ fn destroy_luks_header(device: &str) -> io::Result<()> {
     Ok(())
 }
 
-fn verify_wipe(device: &str) -> io::Result<()> {
-    println!("🔍 Reading first 100MB to verify randomness...");
-    
-    let output = ProcessCommand::new("dd")
-        .args(&[
-            &format!("if={}", device),
-            "of=/dev/null",
-            "bs=1M",
-            "count=100",
-            "status=progress"
-        ])
-        .output()?;
+/// What each part of the wiped target should now hold, for the sampling
+/// verification. Offsets are relative to `target`.
+fn verification_plan(target: &str, options: &WipeOptions, range: Option<&ByteRange>, defects: &[ByteRange]) -> io::Result<VerifyPlan> {
+    let size = range::device_size(target)?;
+    let device_offset = range.map_or(0, |r| r.start);
+    let header = match options.method {
+        // destroy_luks_header zeroes the header area; everything after it is ciphertext
+        WipeMethod::Luks2 => LUKS_HEADER_WIPE.min(size),
+        _ => 0,
+    };
+    // A ChaCha20 stream is checked statistically like ciphertext
+    let body = match options.method {
+        WipeMethod::Zeros => Expected::Fill(0x00),
+        WipeMethod::Ones => Expected::Fill(0xff),
+        _ => Expected::Random,
+    };
 
-    if output.status.success() {
-        println!("✅ Device appears to contain random data");
-    } else {
-        println!("⚠️  Verification completed with warnings");
+    let mut regions = Vec::new();
+    if header > 0 {
+        regions.push((ByteRange { start: 0, end: header }, Expected::Fill(0)));
+    }
+    if header < size {
+        regions.push((ByteRange { start: header, end: size }, body));
     }
 
-    Ok(())
+    Ok(VerifyPlan {
+        regions,
+        skip: target_defects(defects, device_offset),
+        coverage_percent: options.verify_coverage,
+        device_offset,
+    })
 }
 
-fn generate_completion_report(device: &str, wipe_id: &Uuid) {
-    let separator = "═".repeat(60);
-    println!("\n{}", separator);
-    println!("📋 LUKS CRYPTO WIPE COMPLETION REPORT");
-    println!("{}", separator);
-    println!("🆔 Operation ID: {}", wipe_id);
-    println!("📱 Device: {}", device);
-    println!("🔐 Method: LUKS2 AES-XTS-256 Encryption");
-    println!("🗝️  Key Size: 512 bits");
-    println!("🧂 Hash: SHA-256");
-    println!("🔄 Process:");
-    println!("   1. ✅ LUKS encryption applied");
-    println!("   2. ✅ Filled with encrypted random data");
-    println!("   3. ✅ Encryption keys destroyed");
-    println!("   4. ✅ LUKS header overwritten");
-    println!("🛡️  Security: Data is cryptographically unrecoverable");
-    
-    // Use system time instead of chrono
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    println!("🕒 Completed: {} (Unix timestamp)", timestamp);
-    
-    println!("{}", separator);
-    println!("\n🎉 Mission accomplished! Your data is gone forever! 🎉");
+/// Defects (device offsets) translated to offsets within a range target
+/// starting at `device_offset`.
+fn target_defects(defects: &[ByteRange], device_offset: u64) -> Vec<ByteRange> {
+    defects
+        .iter()
+        .map(|d| ByteRange { start: d.start.saturating_sub(device_offset), end: d.end.saturating_sub(device_offset) })
+        .collect()
 }
@@ -0,0 +1,219 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command as ProcessCommand, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct JobResult {
+    pub device: String,
+    pub operation_id: Option<String>,
+    pub success: bool,
+    pub duration: Duration,
+}
+
+const OPERATION_ID_PREFIX: &str = "🆔 Operation ID: ";
+
+fn job_tag(device: &str) -> String {
+    device.strip_prefix("/dev/").unwrap_or(device).to_string()
+}
+
+/// Copy a child's output to our own, one line at a time with the job tag in
+/// front. `\r` progress updates are treated as line ends so they are not
+/// buffered until the job finishes.
+fn forward_output<R: Read>(
+    mut source: R,
+    tag: String,
+    to_stderr: bool,
+    operation_id: Arc<Mutex<Option<String>>>,
+) {
+    let mut buffer = [0u8; 4096];
+    let mut line = Vec::new();
+
+    let emit = |line: &mut Vec<u8>| {
+        if line.is_empty() {
+            return;
+        }
+        let text = String::from_utf8_lossy(line).to_string();
+        if let Some(id) = text.trim().strip_prefix(OPERATION_ID_PREFIX) {
+            *operation_id.lock().unwrap() = Some(id.trim().to_string());
+        }
+        if to_stderr {
+            eprintln!("[{}] {}", tag, text);
+        } else {
+            println!("[{}] {}", tag, text);
+        }
+        line.clear();
+    };
+
+    loop {
+        let n = match source.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        for &byte in &buffer[..n] {
+            if byte == b'\n' || byte == b'\r' {
+                emit(&mut line);
+            } else {
+                line.push(byte);
+            }
+        }
+    }
+    emit(&mut line);
+}
+
+/// Run one wipe job as a child process of this executable so every device
+/// gets its own operation ID, report and failure isolation.
+pub fn run_job(device: String, forwarded: Vec<String>) -> JobResult {
+    let started = Instant::now();
+    let tag = job_tag(&device);
+    let operation_id = Arc::new(Mutex::new(None));
+
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!("[{}] ❌ Cannot locate wipeshit executable: {}", tag, e);
+            return JobResult { device, operation_id: None, success: false, duration: started.elapsed() };
+        }
+    };
+
+    let child = ProcessCommand::new(exe)
+        .arg(&device)
+        .arg("--force")
+        .args(&forwarded)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("[{}] ❌ Failed to start wipe job: {}", tag, e);
+            return JobResult { device, operation_id: None, success: false, duration: started.elapsed() };
+        }
+    };
+
+    let stdout = child.stdout.take().map(|out| {
+        let (tag, id) = (tag.clone(), Arc::clone(&operation_id));
+        thread::spawn(move || forward_output(out, tag, false, id))
+    });
+    let stderr = child.stderr.take().map(|err| {
+        let (tag, id) = (tag.clone(), Arc::clone(&operation_id));
+        thread::spawn(move || forward_output(err, tag, true, id))
+    });
+
+    let success = child.wait().map(|status| status.success()).unwrap_or(false);
+    for reader in [stdout, stderr].into_iter().flatten() {
+        let _ = reader.join();
+    }
+
+    let operation_id = operation_id.lock().unwrap().take();
+    JobResult { device, operation_id, success, duration: started.elapsed() }
+}
+
+/// `device` and every block device it sits on (the disk of a partition, the
+/// members of a dm or md device), as canonical paths.
+pub fn device_stack(device: &str) -> io::Result<Vec<PathBuf>> {
+    let output = ProcessCommand::new("lsblk")
+        .args(["-n", "-l", "-s", "-o", "PATH", device])
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "lsblk cannot resolve {}: {}", device, String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let mut stack = vec![fs::canonicalize(device)?];
+    for path in String::from_utf8_lossy(&output.stdout).lines().map(str::trim).filter(|line| !line.is_empty()) {
+        stack.push(fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path)));
+    }
+    Ok(stack)
+}
+
+/// Whether two device stacks share media: the same device, or one sitting
+/// on the other like a partition on its disk.
+pub fn overlaps(stack: &[PathBuf], other: &[PathBuf]) -> bool {
+    stack.contains(&other[0]) || other.contains(&stack[0])
+}
+
+/// The devices to wipe, each once: the same device given twice (also via a
+/// symlink such as /dev/disk/by-id) is dropped, and a device listed together
+/// with one it sits on, like a disk and one of its partitions, is refused,
+/// since concurrent jobs on the same media would destroy each other's work.
+pub fn distinct_devices(devices: &[String]) -> io::Result<Vec<String>> {
+    let mut distinct: Vec<(String, Vec<PathBuf>)> = Vec::new();
+    for device in devices {
+        let stack = device_stack(device)?;
+        if let Some((first, _)) = distinct.iter().find(|(_, other)| other[0] == stack[0]) {
+            println!("⚠️  {} is the same device as {} - wiping it once", device, first);
+            continue;
+        }
+        if let Some((other, _)) = distinct.iter().find(|(_, other)| overlaps(&stack, other)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} and {} are on the same media - wipe the whole disk only", device, other)
+            ));
+        }
+        distinct.push((device.clone(), stack));
+    }
+    Ok(distinct.into_iter().map(|(device, _)| device).collect())
+}
+
+/// Wipe several devices concurrently, one job per device.
+pub fn run_parallel_wipes(devices: &[String], forwarded: &[String]) -> io::Result<Vec<JobResult>> {
+    println!("🚀 Starting {} wipe jobs in parallel...", devices.len());
+    for device in devices {
+        println!("   [{}] {}", job_tag(device), device);
+    }
+    println!();
+    io::stdout().flush()?;
+
+    let handles: Vec<_> = devices
+        .iter()
+        .map(|device| {
+            let (device, forwarded) = (device.clone(), forwarded.to_vec());
+            thread::spawn(move || run_job(device, forwarded))
+        })
+        .collect();
+
+    let results = handles
+        .into_iter()
+        .zip(devices)
+        .map(|(handle, device)| {
+            handle.join().unwrap_or_else(|_| JobResult {
+                device: device.clone(),
+                operation_id: None,
+                success: false,
+                duration: Duration::ZERO,
+            })
+        })
+        .collect();
+
+    Ok(results)
+}
+
+pub fn print_summary(results: &[JobResult]) {
+    let separator = "═".repeat(60);
+    let succeeded = results.iter().filter(|r| r.success).count();
+
+    println!("\n{}", separator);
+    println!("📋 PARALLEL WIPE SUMMARY");
+    println!("{}", separator);
+    for result in results {
+        println!("{} {} - {} - {}s",
+            if result.success { "✅" } else { "❌" },
+            result.device,
+            result.operation_id.as_deref().unwrap_or("no operation ID"),
+            result.duration.as_secs()
+        );
+    }
+    println!("{}", separator);
+    println!("🎯 {} of {} devices wiped successfully", succeeded, results.len());
+    if succeeded < results.len() {
+        println!("⚠️  Check the job output above for the failed devices");
+    }
+    println!("{}", separator);
+}
@@ -0,0 +1,39 @@
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+/// Data written by an overwrite pass. A stream is regenerated from its seed
+/// for any offset, so resumed writes and read-back verification see exactly
+/// the bytes of an uninterrupted pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pattern {
+    Fill(u8),
+    Stream([u8; 32]),
+}
+
+impl Pattern {
+    /// Fill `buf` with the pattern bytes for target offsets
+    /// `offset..offset + buf.len()`. Offsets must be multiples of 4.
+    pub fn fill(&self, buf: &mut [u8], offset: u64) {
+        match self {
+            Pattern::Fill(byte) => buf.fill(*byte),
+            Pattern::Stream(seed) => {
+                let mut rng = ChaCha20Rng::from_seed(*seed);
+                rng.set_word_pos((offset / 4) as u128);
+                rng.fill_bytes(buf);
+            }
+        }
+    }
+
+    /// Whether `fill` produces the same bytes at every offset, so a buffer
+    /// can be filled once and reused.
+    pub fn is_constant(&self) -> bool {
+        matches!(self, Pattern::Fill(_))
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Pattern::Fill(byte) => format!("0x{:02x} fill", byte),
+            Pattern::Stream(_) => "ChaCha20 stream from a CSPRNG seed".to_string(),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Just enough PDF to print a certificate: A4 pages of Helvetica text and
+// ruled lines, no external crates.
+
+pub const PAGE_WIDTH: f32 = 595.0;
+pub const PAGE_HEIGHT: f32 = 842.0;
+
+/// Content stream of one page. Coordinates are in points from the bottom left.
+#[derive(Default)]
+pub struct Page {
+    ops: String,
+}
+
+/// Text as a PDF string literal in WinAnsiEncoding; characters outside
+/// Latin-1 are replaced, since the standard fonts cannot show them.
+fn literal(text: &str) -> String {
+    let mut out = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => out.push_str(&format!("\\{:03o}", c as u32)),
+            _ => out.push('?'),
+        }
+    }
+    out.push(')');
+    out
+}
+
+impl Page {
+    pub fn text(&mut self, x: f32, y: f32, size: f32, bold: bool, text: &str) {
+        let font = if bold { "F2" } else { "F1" };
+        self.ops.push_str(&format!("BT /{} {} Tf {} {} Td {} Tj ET\n", font, size, x, y, literal(text)));
+    }
+
+    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        self.ops.push_str(&format!("0.5 w {} {} m {} {} l S\n", x1, y1, x2, y2));
+    }
+}
+
+/// Serialise `pages` into a complete PDF file.
+pub fn render(pages: &[Page], title: &str) -> Vec<u8> {
+    // Objects: 1 catalog, 2 page tree, 3 info, 4-5 fonts, then page + content pairs
+    let mut objects: Vec<String> = Vec::new();
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 6 + 2 * i).collect();
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>",
+        page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "), pages.len()));
+    objects.push(format!("<< /Title {} /Producer {} >>",
+        literal(title), literal(concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")))));
+    objects.push("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string());
+    objects.push("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_string());
+    for (page, id) in pages.iter().zip(&page_ids) {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, id + 1));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", page.ops.len(), page.ops));
+    }
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+    let xref = out.len();
+    out.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+    for offset in offsets {
+        out.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    out.push_str(&format!("trailer\n<< /Size {} /Root 1 0 R /Info 3 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1, xref));
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xref_offsets_point_at_their_objects() {
+        let mut first = Page::default();
+        first.text(56.0, 700.0, 12.0, true, "Certificate (1/2)");
+        first.line(56.0, 696.0, 539.0, 696.0);
+        let mut second = Page::default();
+        second.text(56.0, 700.0, 10.0, false, "Signature");
+        let pdf = render(&[first, second], "Wipe certificate");
+        let text = String::from_utf8(pdf.clone()).unwrap();
+
+        let startxref: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        assert!(text[startxref..].starts_with("xref\n0 10\n0000000000 65535 f \n"));
+        let entries: Vec<&str> = text[startxref..].lines().skip(3).take(9).collect();
+        for (i, entry) in entries.iter().enumerate() {
+            assert_eq!(entry.len(), 19, "{:?}", entry);
+            assert!(entry.ends_with(" 00000 n "));
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj\n", i + 1)), "object {} at {}", i + 1, offset);
+        }
+        assert!(text.contains("/Size 10 /Root 1 0 R"));
+        assert!(text.ends_with("%%EOF\n"));
+    }
+}
@@ -0,0 +1,212 @@
+use std::io;
+use std::process::Command as ProcessCommand;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PartitionTable {
+    Gpt,
+    Mbr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filesystem {
+    Exfat,
+    Ntfs,
+    Ext4,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProvisionSpec {
+    pub table: PartitionTable,
+    pub filesystem: Filesystem,
+    pub label: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProvisionRecord {
+    pub table: Option<PartitionTable>,
+    pub filesystem: Filesystem,
+    pub label: String,
+    pub partition: String,
+    pub fs_uuid: Option<String>,
+}
+
+pub const DEFAULT_LABEL: &str = "DRIVE";
+
+impl PartitionTable {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "gpt" => Some(PartitionTable::Gpt),
+            "mbr" | "msdos" | "dos" => Some(PartitionTable::Mbr),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PartitionTable::Gpt => "GPT",
+            PartitionTable::Mbr => "MBR",
+        }
+    }
+
+    fn parted_label(&self) -> &'static str {
+        match self {
+            PartitionTable::Gpt => "gpt",
+            PartitionTable::Mbr => "msdos",
+        }
+    }
+}
+
+impl Filesystem {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "exfat" => Some(Filesystem::Exfat),
+            "ntfs" => Some(Filesystem::Ntfs),
+            "ext4" => Some(Filesystem::Ext4),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Filesystem::Exfat => "exFAT",
+            Filesystem::Ntfs => "NTFS",
+            Filesystem::Ext4 => "ext4",
+        }
+    }
+
+    fn max_label_len(&self) -> usize {
+        match self {
+            Filesystem::Exfat => 11,
+            Filesystem::Ntfs => 32,
+            Filesystem::Ext4 => 16,
+        }
+    }
+
+    // parted only knows a handful of filesystem names; "ntfs" gives the
+    // Microsoft basic data type which Windows and macOS expect for exFAT too
+    fn parted_type(&self) -> &'static str {
+        match self {
+            Filesystem::Exfat | Filesystem::Ntfs => "ntfs",
+            Filesystem::Ext4 => "ext4",
+        }
+    }
+
+    fn mkfs_command(&self, partition: &str, label: &str) -> ProcessCommand {
+        let mut command = match self {
+            Filesystem::Exfat => ProcessCommand::new("mkfs.exfat"),
+            Filesystem::Ntfs => ProcessCommand::new("mkfs.ntfs"),
+            Filesystem::Ext4 => ProcessCommand::new("mkfs.ext4"),
+        };
+        match self {
+            Filesystem::Exfat => command.args(["-L", label, partition]),
+            Filesystem::Ntfs => command.args(["--fast", "--force", "-L", label, partition]),
+            Filesystem::Ext4 => command.args(["-F", "-L", label, partition]),
+        };
+        command
+    }
+}
+
+impl ProvisionSpec {
+    pub fn new(filesystem: &str, table: Option<&str>, label: Option<&str>) -> io::Result<Self> {
+        let filesystem = Filesystem::parse(filesystem).ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unsupported filesystem '{}' (use exfat, ntfs or ext4)", filesystem)
+        ))?;
+
+        let table = match table {
+            Some(value) => PartitionTable::parse(value).ok_or_else(|| io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unsupported partition table '{}' (use gpt or mbr)", value)
+            ))?,
+            None => PartitionTable::Gpt,
+        };
+
+        let label = label.unwrap_or(DEFAULT_LABEL).to_string();
+        if label.is_empty() || label.len() > filesystem.max_label_len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} labels must be 1-{} characters", filesystem.name(), filesystem.max_label_len())
+            ));
+        }
+
+        Ok(ProvisionSpec { table, filesystem, label })
+    }
+}
+
+fn run_tool(command: &mut ProcessCommand, what: &str) -> io::Result<()> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            format!("{} failed: {}", what, String::from_utf8_lossy(&output.stderr).trim())
+        ));
+    }
+    Ok(())
+}
+
+fn is_partition(device: &str) -> bool {
+    let device_name = device.strip_prefix("/dev/").unwrap_or(device);
+    std::path::Path::new(&format!("/sys/class/block/{}/partition", device_name)).exists()
+}
+
+/// Path of the first partition, e.g. /dev/sdb1 or /dev/nvme0n1p1.
+fn first_partition(device: &str) -> String {
+    if device.chars().last().is_some_and(|c| c.is_ascii_digit()) {
+        format!("{}p1", device)
+    } else {
+        format!("{}1", device)
+    }
+}
+
+fn settle_partitions(device: &str) {
+    let _ = ProcessCommand::new("partprobe").arg(device).status();
+    let _ = ProcessCommand::new("udevadm").arg("settle").status();
+    thread::sleep(Duration::from_secs(1));
+}
+
+fn filesystem_uuid(partition: &str) -> Option<String> {
+    let output = ProcessCommand::new("blkid")
+        .args(["-s", "UUID", "-o", "value", partition])
+        .output()
+        .ok()?;
+    let uuid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if uuid.is_empty() { None } else { Some(uuid) }
+}
+
+/// Lay down a fresh partition table with a single partition spanning the
+/// drive and format it, so a wiped drive is ready for resale.
+pub fn provision_device(device: &str, spec: &ProvisionSpec) -> io::Result<ProvisionRecord> {
+    // A wiped partition only gets a filesystem; the surrounding table belongs
+    // to the rest of the drive
+    let (table, partition) = if is_partition(device) {
+        println!("📂 Target is a partition - creating filesystem only");
+        (None, device.to_string())
+    } else {
+        println!("🗂️  Creating {} partition table...", spec.table.name());
+        run_tool(
+            ProcessCommand::new("parted").args([
+                "-s", "-a", "optimal", device,
+                "mklabel", spec.table.parted_label(),
+                "mkpart", "primary", spec.filesystem.parted_type(), "1MiB", "100%",
+            ]),
+            "parted",
+        )?;
+        settle_partitions(device);
+        (Some(spec.table), first_partition(device))
+    };
+
+    println!("💿 Formatting {} as {} (label '{}')...", partition, spec.filesystem.name(), spec.label);
+    run_tool(
+        &mut spec.filesystem.mkfs_command(&partition, &spec.label),
+        &format!("mkfs ({})", spec.filesystem.name()),
+    )?;
+
+    Ok(ProvisionRecord {
+        table,
+        filesystem: spec.filesystem,
+        label: spec.label.clone(),
+        fs_uuid: filesystem_uuid(&partition),
+        partition,
+    })
+}
@@ -0,0 +1,407 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use uuid::Uuid;
+use crate::checkpoint::{unix_now, write_atomically};
+use crate::identity::{self, DeviceIdentity, Resolution};
+use crate::parallel::{self, JobResult};
+
+pub const DEFAULT_QUEUE_DIR: &str = "/var/lib/wipeshit/queue";
+pub const DEFAULT_CONCURRENCY: usize = 4;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    /// The drive could not be identified safely; an operator has to remove
+    /// the job and resubmit it for the right device
+    Attention,
+}
+
+impl JobState {
+    fn name(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Done => "done",
+            JobState::Failed => "failed",
+            JobState::Attention => "attention",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "queued" => Some(JobState::Queued),
+            "running" => Some(JobState::Running),
+            "done" => Some(JobState::Done),
+            "failed" => Some(JobState::Failed),
+            "attention" => Some(JobState::Attention),
+            _ => None,
+        }
+    }
+}
+
+/// A submitted wipe, persisted as one file in the queue directory.
+#[derive(Debug, Clone)]
+pub struct QueuedJob {
+    pub job_id: String,
+    pub device: String,
+    pub identity: DeviceIdentity,
+    pub wwn: Option<String>,
+    pub method: String,
+    pub priority: i32,
+    pub state: JobState,
+    pub options: Vec<String>,
+    pub submitted: u64,
+    pub started: Option<u64>,
+    pub finished: Option<u64>,
+    pub attempts: u32,
+    pub operation_id: Option<String>,
+    /// Why the job needs attention
+    pub note: Option<String>,
+}
+
+pub struct JobQueue {
+    dir: PathBuf,
+}
+
+impl JobQueue {
+    pub fn open(dir: &str) -> io::Result<Self> {
+        let dir = PathBuf::from(dir);
+        fs::create_dir_all(&dir)?;
+        Ok(JobQueue { dir })
+    }
+
+    fn path_for(&self, job_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.job", job_id))
+    }
+
+    pub fn save(&self, job: &QueuedJob) -> io::Result<()> {
+        write_atomically(&self.path_for(&job.job_id), format_job(job))
+    }
+
+    pub fn jobs(&self) -> io::Result<Vec<QueuedJob>> {
+        let mut jobs = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("job") {
+                continue;
+            }
+            match parse_job(&fs::read_to_string(&path)?) {
+                Some(job) => jobs.push(job),
+                None => println!("⚠️  Ignoring corrupt job file {}", path.display()),
+            }
+        }
+        // Highest priority first, oldest submission first within a priority
+        jobs.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.submitted.cmp(&b.submitted)));
+        Ok(jobs)
+    }
+
+    pub fn submit(&self, device: &str, method: &str, priority: i32, options: &[String]) -> io::Result<QueuedJob> {
+        let job = QueuedJob {
+            job_id: Uuid::new_v4().simple().to_string()[..12].to_string(),
+            device: device.to_string(),
+            identity: identity::read_identity(device),
+            wwn: identity::read_wwn(device),
+            method: method.to_string(),
+            priority,
+            state: JobState::Queued,
+            options: options.to_vec(),
+            submitted: unix_now(),
+            started: None,
+            finished: None,
+            attempts: 0,
+            operation_id: None,
+            note: None,
+        };
+        self.save(&job)?;
+        Ok(job)
+    }
+
+    /// Refuse `device` if a job that is still queued or running targets the
+    /// same media, e.g. the disk of a queued partition.
+    pub fn check_overlap(&self, device: &str) -> io::Result<()> {
+        let stack = parallel::device_stack(device)?;
+        for job in self.jobs()? {
+            if !matches!(job.state, JobState::Queued | JobState::Running) {
+                continue;
+            }
+            if parallel::overlaps(&stack, &device_stack(&job.device)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is on the same media as {} of {} job {}", device, job.device, job.state.name(), job.job_id)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove(&self, job_id: &str) -> io::Result<()> {
+        let job = self.jobs()?.into_iter().find(|job| job.job_id == job_id).ok_or_else(|| io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No job {} in the queue", job_id)
+        ))?;
+        if job.state == JobState::Running {
+            return Err(io::Error::other(
+                format!("Job {} is running and cannot be removed", job_id)
+            ));
+        }
+        fs::remove_file(self.path_for(job_id))
+    }
+
+    /// Only one scheduler may dispatch from a queue at a time.
+    fn lock(&self) -> io::Result<SchedulerLock> {
+        let path = self.dir.join("scheduler.lock");
+        if let Ok(content) = fs::read_to_string(&path) {
+            let pid = content.trim();
+            if !pid.is_empty() && Path::new(&format!("/proc/{}", pid)).exists() {
+                return Err(io::Error::other(
+                    format!("Another scheduler (PID {}) is already running this queue", pid)
+                ));
+            }
+            // Stale lock left behind by a scheduler that died
+            fs::remove_file(&path)?;
+        }
+
+        let mut file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+        writeln!(file, "{}", std::process::id())?;
+        Ok(SchedulerLock { path })
+    }
+}
+
+struct SchedulerLock {
+    path: PathBuf,
+}
+
+impl Drop for SchedulerLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn format_job(job: &QueuedJob) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("job_id={}\n", job.job_id));
+    out.push_str(&format!("device={}\n", job.device));
+    out.push_str(&format!("model={}\n", job.identity.model));
+    out.push_str(&format!("serial={}\n", job.identity.serial));
+    out.push_str(&format!("size_bytes={}\n", job.identity.size_bytes));
+    if let Some(wwn) = &job.wwn {
+        out.push_str(&format!("wwn={}\n", wwn));
+    }
+    out.push_str(&format!("method={}\n", job.method));
+    out.push_str(&format!("priority={}\n", job.priority));
+    out.push_str(&format!("state={}\n", job.state.name()));
+    out.push_str(&format!("submitted={}\n", job.submitted));
+    out.push_str(&format!("attempts={}\n", job.attempts));
+    if let Some(started) = job.started {
+        out.push_str(&format!("started={}\n", started));
+    }
+    if let Some(finished) = job.finished {
+        out.push_str(&format!("finished={}\n", finished));
+    }
+    if let Some(operation_id) = &job.operation_id {
+        out.push_str(&format!("operation_id={}\n", operation_id));
+    }
+    if let Some(note) = &job.note {
+        out.push_str(&format!("note={}\n", note));
+    }
+    for option in &job.options {
+        out.push_str(&format!("option={}\n", option));
+    }
+    out
+}
+
+fn parse_job(content: &str) -> Option<QueuedJob> {
+    let mut job = QueuedJob {
+        job_id: String::new(),
+        device: String::new(),
+        identity: DeviceIdentity { model: String::new(), serial: String::new(), size_bytes: 0 },
+        wwn: None,
+        method: String::new(),
+        priority: 0,
+        state: JobState::Queued,
+        options: Vec::new(),
+        submitted: 0,
+        started: None,
+        finished: None,
+        attempts: 0,
+        operation_id: None,
+        note: None,
+    };
+
+    for line in content.lines() {
+        let (key, value) = line.split_once('=')?;
+        match key {
+            "job_id" => job.job_id = value.to_string(),
+            "device" => job.device = value.to_string(),
+            "model" => job.identity.model = value.to_string(),
+            "serial" => job.identity.serial = value.to_string(),
+            "size_bytes" => job.identity.size_bytes = value.parse().ok()?,
+            "wwn" => job.wwn = Some(value.to_string()),
+            "method" => job.method = value.to_string(),
+            "priority" => job.priority = value.parse().ok()?,
+            "state" => job.state = JobState::parse(value)?,
+            "submitted" => job.submitted = value.parse().ok()?,
+            "attempts" => job.attempts = value.parse().ok()?,
+            "started" => job.started = Some(value.parse().ok()?),
+            "finished" => job.finished = Some(value.parse().ok()?),
+            "operation_id" => job.operation_id = Some(value.to_string()),
+            "note" => job.note = Some(value.to_string()),
+            "option" => job.options.push(value.to_string()),
+            _ => {}
+        }
+    }
+
+    if job.job_id.is_empty() {
+        return None;
+    }
+    Some(job)
+}
+
+pub fn print_queue(queue: &JobQueue) -> io::Result<()> {
+    let jobs = queue.jobs()?;
+    println!("\n📋 Wipe Queue ({} jobs)", jobs.len());
+    println!("═══════════════════════════");
+    if jobs.is_empty() {
+        println!("   (empty)");
+        return Ok(());
+    }
+    for job in &jobs {
+        let icon = match job.state {
+            JobState::Queued => "⏳",
+            JobState::Running => "🔄",
+            JobState::Done => "✅",
+            JobState::Failed => "❌",
+            JobState::Attention => "🙋",
+        };
+        println!("{} {} [{}] prio {} - {} ({} / {}) - {}{}",
+            icon,
+            job.job_id,
+            job.state.name(),
+            job.priority,
+            job.device,
+            job.identity.model,
+            job.identity.serial,
+            job.method,
+            job.operation_id.as_ref().map(|id| format!(" - op {}", id)).unwrap_or_default()
+        );
+        if let Some(note) = &job.note {
+            println!("   {}", note);
+        }
+    }
+    Ok(())
+}
+
+/// Stack of a job's device; a drive that has gone away only blocks its own path.
+fn device_stack(device: &str) -> Vec<PathBuf> {
+    parallel::device_stack(device).unwrap_or_else(|_| vec![PathBuf::from(device)])
+}
+
+/// Dispatch queued jobs with at most `concurrency` running at once. Jobs
+/// found in the running state were cut off by a previous scheduler and are
+/// resumed from their checkpoints. With `drain` the scheduler exits once
+/// nothing is left to do, otherwise it keeps polling for new submissions.
+pub fn run_scheduler(queue: &JobQueue, concurrency: usize, drain: bool) -> io::Result<()> {
+    let _lock = queue.lock()?;
+    let concurrency = concurrency.max(1);
+
+    for mut job in queue.jobs()? {
+        if job.state == JobState::Running {
+            println!("♻️  Job {} was interrupted - requeueing with --resume", job.job_id);
+            job.state = JobState::Queued;
+            if !job.options.iter().any(|o| o == "--resume") {
+                job.options.push("--resume".to_string());
+            }
+            queue.save(&job)?;
+        }
+    }
+
+    println!("🗓️  Scheduler started (concurrency {}, queue {})", concurrency, queue.dir.display());
+    let mut running: Vec<(QueuedJob, Vec<PathBuf>, thread::JoinHandle<JobResult>)> = Vec::new();
+
+    loop {
+        // Collect finished workers
+        let mut index = 0;
+        while index < running.len() {
+            if !running[index].2.is_finished() {
+                index += 1;
+                continue;
+            }
+            let (mut job, _, handle) = running.remove(index);
+            let result = handle.join().ok();
+            let success = result.as_ref().is_some_and(|r| r.success);
+            job.state = if success { JobState::Done } else { JobState::Failed };
+            job.finished = Some(unix_now());
+            job.operation_id = result.and_then(|r| r.operation_id).or(job.operation_id);
+            queue.save(&job)?;
+            println!("{} Job {} on {} {}",
+                if success { "✅" } else { "❌" },
+                job.job_id, job.device,
+                if success { "completed" } else { "failed" });
+        }
+
+        // Start new workers; a job waits while anything on the same media
+        // runs, a partition of a busy disk included
+        let mut busy: Vec<Vec<PathBuf>> = running.iter().map(|(_, stack, _)| stack.clone()).collect();
+        let mut waiting = false;
+        let mut missing = Vec::new();
+        for mut job in queue.jobs()? {
+            if running.len() >= concurrency {
+                waiting = true;
+                break;
+            }
+            if job.state != JobState::Queued {
+                continue;
+            }
+            let (device, stack) = match identity::find_device(&job.identity, job.wwn.as_deref(), &job.device) {
+                Resolution::Found(device) => {
+                    let stack = device_stack(&device);
+                    if busy.iter().any(|other| parallel::overlaps(&stack, other)) {
+                        waiting = true;
+                        continue;
+                    }
+                    (device, stack)
+                }
+                Resolution::NotConnected => {
+                    missing.push(job.job_id.clone());
+                    continue;
+                }
+                Resolution::NeedsAttention(reason) => {
+                    println!("🙋 Job {} needs an operator: {} - remove it and resubmit it for the right device",
+                        job.job_id, reason);
+                    job.state = JobState::Attention;
+                    job.note = Some(reason);
+                    queue.save(&job)?;
+                    continue;
+                }
+            };
+            busy.push(stack.clone());
+
+            job.device = device.clone();
+            job.state = JobState::Running;
+            job.started = Some(unix_now());
+            job.attempts += 1;
+            queue.save(&job)?;
+            println!("🚀 Dispatching job {} (prio {}) on {}", job.job_id, job.priority, device);
+
+            let options = job.options.clone();
+            let handle = thread::spawn(move || parallel::run_job(device, options));
+            running.push((job, stack, handle));
+        }
+
+        if drain && running.is_empty() && !waiting {
+            if !missing.is_empty() {
+                println!("⚠️  Jobs left queued because their drive is not connected: {}", missing.join(", "));
+            }
+            println!("🏁 Queue drained");
+            return Ok(());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,172 @@
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom};
+use std::process::Command as ProcessCommand;
+
+// device-mapper tables are always expressed in 512-byte sectors
+pub const DM_SECTOR: u64 = 512;
+
+/// Half-open byte range [start, end) of a device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+fn parse_position(value: &str, sector_size: u64) -> Option<u64> {
+    let value = value.trim();
+    let (number, multiplier) = match value.char_indices().last()? {
+        (i, 's') | (i, 'S') => (&value[..i], sector_size),
+        (i, 'K') | (i, 'k') => (&value[..i], 1 << 10),
+        (i, 'M') | (i, 'm') => (&value[..i], 1 << 20),
+        (i, 'G') | (i, 'g') => (&value[..i], 1 << 30),
+        (i, 'T') | (i, 't') => (&value[..i], 1 << 40),
+        _ => (value, 1),
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+pub fn device_size(device: &str) -> io::Result<u64> {
+    File::open(device)?.seek(SeekFrom::End(0))
+}
+
+impl ByteRange {
+    /// Parse `START:END` where each side is bytes, or carries a unit suffix:
+    /// `s` for sectors, or K/M/G/T (binary). An empty END means end of device.
+    pub fn parse(value: &str, sector_size: u64, device_size: u64) -> io::Result<Self> {
+        let invalid = |reason: &str| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid range '{}': {}", value, reason)
+        );
+
+        let (start, end) = value.split_once(':').ok_or_else(|| invalid("expected START:END"))?;
+        let start = parse_position(start, sector_size).ok_or_else(|| invalid("bad START"))?;
+        let end = if end.trim().is_empty() {
+            device_size
+        } else {
+            parse_position(end, sector_size).ok_or_else(|| invalid("bad END"))?
+        };
+
+        if start >= end {
+            return Err(invalid("END must be after START"));
+        }
+        if end > device_size {
+            return Err(invalid(&format!("END is beyond the end of the device ({} bytes)", device_size)));
+        }
+        if start % sector_size != 0 || end % sector_size != 0 {
+            return Err(invalid(&format!("START and END must be multiples of the {}-byte sector size", sector_size)));
+        }
+
+        Ok(ByteRange { start, end })
+    }
+
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    pub fn describe(&self, sector_size: u64) -> String {
+        format!("bytes {}-{} (sectors {}-{} of {} B, {} bytes)",
+            self.start, self.end - 1,
+            self.start / sector_size, self.end / sector_size - 1,
+            sector_size, self.len())
+    }
+}
+
+/// A device-mapper linear target exposing only the range, so any wipe method
+/// can run on it as if it were a whole device. Removed again on drop.
+pub struct RangeMapping {
+    pub name: String,
+}
+
+impl RangeMapping {
+    pub fn create(device: &str, range: &ByteRange, name: &str) -> io::Result<Self> {
+        let table = format!("0 {} linear {} {}",
+            range.len() / DM_SECTOR, device, range.start / DM_SECTOR);
+
+        let output = ProcessCommand::new("dmsetup")
+            .args(["create", name, "--table", &table])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(io::Error::other(
+                format!("Failed to map range: {}", String::from_utf8_lossy(&output.stderr).trim())
+            ));
+        }
+
+        Ok(RangeMapping { name: name.to_string() })
+    }
+
+    pub fn path(&self) -> String {
+        format!("/dev/mapper/{}", self.name)
+    }
+}
+
+impl Drop for RangeMapping {
+    fn drop(&mut self) {
+        let _ = ProcessCommand::new("dmsetup")
+            .args(["remove", "--retry", &self.name])
+            .output();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: u64 = 1 << 30;
+
+    fn parse(value: &str, sector_size: u64) -> io::Result<ByteRange> {
+        ByteRange::parse(value, sector_size, SIZE)
+    }
+
+    #[test]
+    fn accepts_bytes_sectors_and_binary_units() {
+        assert_eq!(parse("0:4096", 512).unwrap(), ByteRange { start: 0, end: 4096 });
+        assert_eq!(parse("2048s:4096s", 512).unwrap(), ByteRange { start: 1 << 20, end: 1 << 21 });
+        assert_eq!(parse("8S:16S", 4096).unwrap(), ByteRange { start: 32768, end: 65536 });
+        assert_eq!(parse("1k:1M", 512).unwrap(), ByteRange { start: 1024, end: 1 << 20 });
+        assert_eq!(parse("512M:1G", 512).unwrap(), ByteRange { start: 1 << 29, end: 1 << 30 });
+        assert_eq!(parse(" 1M : 2M ", 512).unwrap(), ByteRange { start: 1 << 20, end: 2 << 20 });
+        // Empty END runs to the end of the device
+        assert_eq!(parse("1M:", 512).unwrap(), ByteRange { start: 1 << 20, end: SIZE });
+        assert_eq!(ByteRange::parse("0:1T", 512, 1 << 40).unwrap().end, 1 << 40);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for value in ["", "4096", "a:b", "1M:x", "-1:4096", "1.5M:2M", "s:1M", "1X:2M", "1M:2M:3M"] {
+            assert!(parse(value, 512).is_err(), "{:?} was accepted", value);
+        }
+    }
+
+    #[test]
+    fn rejects_empty_or_reversed_ranges() {
+        assert!(parse("1M:1M", 512).is_err());
+        assert!(parse("2M:1M", 512).is_err());
+        assert!(parse(&format!("{}:", SIZE), 512).is_err());
+    }
+
+    #[test]
+    fn rejects_overflow_and_past_end() {
+        assert!(parse("0:18446744073709551615T", 512).is_err());
+        assert!(parse("0:99999999999999999999", 512).is_err());
+        assert!(parse("0:36028797018963968s", 512).is_err());
+        assert!(parse(&format!("0:{}", SIZE + 512), 512).is_err());
+        assert!(parse("0:2G", 512).is_err());
+    }
+
+    #[test]
+    fn rejects_unaligned_bounds() {
+        assert!(parse("100:4096", 512).is_err());
+        assert!(parse("0:4097", 512).is_err());
+        // Aligned to 512 but not to a 4K logical sector
+        assert!(parse("512:8192", 4096).is_err());
+        assert!(parse("4096:8192", 4096).is_ok());
+    }
+
+    #[test]
+    fn describes_inclusive_bounds() {
+        let range = ByteRange { start: 1 << 20, end: 2 << 20 };
+        assert_eq!(range.len(), 1 << 20);
+        assert_eq!(range.describe(512), "bytes 1048576-2097151 (sectors 2048-4095 of 512 B, 1048576 bytes)");
+    }
+}
@@ -0,0 +1,353 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+use crate::checkpoint::{unix_now, ResumeEvent};
+use crate::cipher::CryptParams;
+use crate::disposition::Disposition;
+use crate::entropy::EntropyStatus;
+use crate::geometry::SectorGeometry;
+use crate::identity::{DeviceIdentity, MediaInfo};
+use crate::pattern::Pattern;
+use crate::provision::ProvisionRecord;
+use crate::range::ByteRange;
+use crate::residue::ResidueOutcome;
+use crate::verify::{FullVerifyOutcome, VerifyOutcome};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepStatus {
+    Completed,
+    Failed(String),
+    Skipped(String),
+}
+
+/// One executed (or deliberately skipped) step of a wipe.
+#[derive(Debug, Clone)]
+pub struct StepRecord {
+    pub name: String,
+    pub status: StepStatus,
+    pub started: u64,
+    pub duration: Duration,
+    pub details: Vec<(String, String)>,
+}
+
+/// Everything that actually happened during a wipe. The report is rendered
+/// from this alone, so a step only shows as done if it ran and returned Ok.
+#[derive(Debug, Clone)]
+pub struct WipeRecord {
+    pub operation_id: String,
+    pub device: String,
+    pub identity: DeviceIdentity,
+    pub media: MediaInfo,
+    pub disposition: Disposition,
+    pub method: String,
+    pub geometry: SectorGeometry,
+    pub range: Option<ByteRange>,
+    pub encryption: Option<CryptParams>,
+    pub pattern: Option<Pattern>,
+    pub entropy: Option<EntropyStatus>,
+    pub steps: Vec<StepRecord>,
+    pub bytes_written: u64,
+    pub verified: Option<bool>,
+    pub verification: Option<VerifyOutcome>,
+    pub full_verification: Option<FullVerifyOutcome>,
+    pub residue: Option<ResidueOutcome>,
+    pub resumes: Vec<ResumeEvent>,
+    pub defects: Vec<ByteRange>,
+    pub provisioned: Option<ProvisionRecord>,
+    pub started: u64,
+    pub finished: Option<u64>,
+}
+
+impl WipeRecord {
+    pub fn new(operation_id: &str, device: &str, identity: &DeviceIdentity, method: &str, geometry: SectorGeometry) -> Self {
+        WipeRecord {
+            operation_id: operation_id.to_string(),
+            device: device.to_string(),
+            identity: identity.clone(),
+            media: MediaInfo::default(),
+            disposition: Disposition::default(),
+            method: method.to_string(),
+            geometry,
+            range: None,
+            encryption: None,
+            pattern: None,
+            entropy: None,
+            steps: Vec::new(),
+            bytes_written: 0,
+            verified: None,
+            verification: None,
+            full_verification: None,
+            residue: None,
+            resumes: Vec::new(),
+            defects: Vec::new(),
+            provisioned: None,
+            started: unix_now(),
+            finished: None,
+        }
+    }
+
+    /// Run `step`, recording its timing and outcome. Errors are recorded and
+    /// passed on unchanged.
+    pub fn run_step<T, F>(&mut self, name: &str, step: F) -> io::Result<T>
+    where
+        F: FnOnce() -> io::Result<T>,
+    {
+        let started = unix_now();
+        let timer = Instant::now();
+        let result = step();
+        self.steps.push(StepRecord {
+            name: name.to_string(),
+            status: match &result {
+                Ok(_) => StepStatus::Completed,
+                Err(e) => StepStatus::Failed(e.to_string()),
+            },
+            started,
+            duration: timer.elapsed(),
+            details: Vec::new(),
+        });
+        result
+    }
+
+    pub fn skip_step(&mut self, name: &str, reason: &str) {
+        self.steps.push(StepRecord {
+            name: name.to_string(),
+            status: StepStatus::Skipped(reason.to_string()),
+            started: unix_now(),
+            duration: Duration::ZERO,
+            details: Vec::new(),
+        });
+    }
+
+    /// Attach a key/value detail to the most recent step.
+    pub fn detail(&mut self, key: &str, value: impl ToString) {
+        if let Some(step) = self.steps.last_mut() {
+            step.details.push((key.to_string(), value.to_string()));
+        }
+    }
+
+    pub fn failed_step(&self) -> Option<&StepRecord> {
+        self.steps.iter().find(|step| matches!(step.status, StepStatus::Failed(_)))
+    }
+
+    fn completed_step(&self, name: &str) -> Option<&StepRecord> {
+        self.steps.iter().find(|step| step.name == name && step.status == StepStatus::Completed)
+    }
+
+    fn step_completed(&self, name: &str) -> bool {
+        self.completed_step(name).is_some()
+    }
+
+    /// NIST SP 800-88 Rev. 1 category of the wipe. Every method overwrites the
+    /// user-addressable sectors through the host interface, which is Clear.
+    pub fn nist_category(&self) -> &'static str {
+        if self.range.is_some() { "Clear (selected range only)" } else { "Clear" }
+    }
+
+    /// Sanitization technique in the terms of the NIST sample certificate.
+    /// The crypto methods overwrite with ciphertext under a key that is then
+    /// discarded; no drive-internal encryption is relied on, so it is not
+    /// Crypto Erase.
+    pub fn technique(&self) -> &'static str {
+        if self.pattern.is_some() {
+            "Overwrite (single pass)"
+        } else {
+            "Overwrite (single pass of ciphertext under a discarded random key)"
+        }
+    }
+
+    /// How the wipe was verified, in the terms of the NIST sample certificate.
+    pub fn verification_method(&self) -> String {
+        let method = match (&self.full_verification, &self.verification) {
+            (Some(_), _) => "Full (every written sector read back and compared)".to_string(),
+            (None, Some(outcome)) => format!("Quick sampling ({:.3}% of the device read back)", outcome.coverage()),
+            (None, None) => "None".to_string(),
+        };
+        match &self.residue {
+            Some(outcome) => format!("{}, plus a {} residue scan", method, outcome.mode.name()),
+            None => method,
+        }
+    }
+
+    /// Every step ran or was skipped on purpose, and every sector was written.
+    pub fn succeeded(&self) -> bool {
+        self.finished.is_some() && self.failed_step().is_none() && self.defects.is_empty()
+    }
+}
+
+// Steps whose completion makes the old data unrecoverable
+pub const STEP_FILL: &str = "Fill with encrypted data";
+pub const STEP_DESTROY_KEYS: &str = "Destroy keys";
+pub const STEP_OVERWRITE: &str = "Overwrite with pattern";
+// A failure here means a key slot survived, undoing the key destruction
+pub const STEP_HEADER_CHECK: &str = "Confirm LUKS headers destroyed";
+
+// Mismatch ranges listed individually before the rest is summarised
+const MAX_LISTED_RANGES: usize = 64;
+
+pub fn print_report(record: &WipeRecord) {
+    let _ = write_report(record, &mut io::stdout());
+}
+
+/// Render the report; `print_report` sends it to stdout.
+pub fn write_report(record: &WipeRecord, out: &mut impl Write) -> io::Result<()> {
+    let separator = "═".repeat(60);
+    writeln!(out, "\n{}", separator)?;
+    writeln!(out, "📋 {} WIPE {} REPORT",
+        if record.pattern.is_some() { "OVERWRITE" } else { "CRYPTO" },
+        if record.finished.is_some() { "COMPLETION" } else { "FAILURE" })?;
+    writeln!(out, "{}", separator)?;
+    writeln!(out, "🆔 Operation ID: {}", record.operation_id)?;
+    writeln!(out, "📱 Device: {}", record.device)?;
+    writeln!(out, "🏷️  Drive: {} {} (serial {}, {} bytes)",
+        record.media.manufacturer, record.identity.model, record.identity.serial, record.identity.size_bytes)?;
+    writeln!(out, "💾 Media type: {}", record.media.media_type)?;
+    writeln!(out, "💽 Sectors: {}", record.geometry.describe())?;
+    let disposition = &record.disposition;
+    writeln!(out, "📦 Media source: {}", disposition.media_source.as_deref().unwrap_or("not recorded"))?;
+    writeln!(out, "🔏 Confidentiality before sanitization: {}",
+        disposition.confidentiality.map(|category| category.name()).unwrap_or("not recorded"))?;
+    writeln!(out, "🧰 Wipe method: {}", record.method)?;
+    writeln!(out, "📐 NIST SP 800-88: {} - {}", record.nist_category(), record.technique())?;
+    writeln!(out, "🛠️  Tool: {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))?;
+    match &record.range {
+        Some(range) => writeln!(out, "📏 Extent: {}", range.describe(record.geometry.logical)),
+        None => writeln!(out, "📏 Extent: entire device"),
+    }?;
+    match &record.encryption {
+        Some(params) => {
+            writeln!(out, "🔐 Encryption: {} {}", params.mode, params.cipher)?;
+            writeln!(out, "🗝️  Key Size: {} bits", params.key_size)?;
+            writeln!(out, "🧂 Key derivation: {}", params.pbkdf)?;
+            if !params.pbkdf_cost.is_empty() {
+                writeln!(out, "   Cost: {}", params.pbkdf_cost)?;
+            }
+        }
+        None if record.pattern.is_some() => {}
+        None => writeln!(out, "🔐 Encryption: not set up")?,
+    }
+    if let Some(pattern) = &record.pattern {
+        writeln!(out, "✏️  Pattern: {}", pattern.describe())?;
+    }
+
+    match &record.entropy {
+        Some(status) => writeln!(out, "🎲 Entropy: {}", status.describe()),
+        None => writeln!(out, "🎲 Entropy: not checked"),
+    }?;
+
+    writeln!(out, "🔄 Process:")?;
+    for (i, step) in record.steps.iter().enumerate() {
+        let (icon, outcome) = match &step.status {
+            StepStatus::Completed => ("✅", format!("started {}, {:.1}s", step.started, step.duration.as_secs_f64())),
+            StepStatus::Failed(error) => ("❌", format!("started {}, FAILED after {:.1}s: {}",
+                step.started, step.duration.as_secs_f64(), error)),
+            StepStatus::Skipped(reason) => ("⏭️ ", format!("skipped - {}", reason)),
+        };
+        writeln!(out, "   {}. {} {} ({})", i + 1, icon, step.name, outcome)?;
+        for (key, value) in &step.details {
+            writeln!(out, "        {}: {}", key, value)?;
+        }
+    }
+    writeln!(out, "💾 Bytes written this run: {}", record.bytes_written)?;
+    writeln!(out, "🔬 Verification method: {}", record.verification_method())?;
+    match record.verified {
+        Some(true) => writeln!(out, "🔍 Verification: passed"),
+        Some(false) => writeln!(out, "🔍 Verification: FAILED"),
+        None => writeln!(out, "🔍 Verification: not performed"),
+    }?;
+    if let Some(outcome) = &record.verification {
+        writeln!(out, "   {}", outcome.describe())?;
+        for failure in &outcome.failures {
+            writeln!(out, "   ❌ offset {} (expected {}): {}", failure.offset, failure.expected.describe(), failure.detail)?;
+        }
+        if outcome.failed_samples > outcome.failures.len() as u64 {
+            writeln!(out, "   ... and {} more failing samples", outcome.failed_samples - outcome.failures.len() as u64)?;
+        }
+    }
+    if let Some(outcome) = &record.full_verification {
+        writeln!(out, "   Full read-back: {}", outcome.describe())?;
+        if let Some(write) = record.completed_step(STEP_OVERWRITE) {
+            writeln!(out, "   ⏱️  Write pass {:.1}s, read-back pass {:.1}s", write.duration.as_secs_f64(),
+                outcome.duration.as_secs_f64())?;
+        }
+        let logical = record.geometry.logical;
+        for (label, ranges) in [("differs from the pattern", &outcome.mismatches), ("unreadable", &outcome.unreadable)] {
+            for range in ranges.iter().take(MAX_LISTED_RANGES) {
+                writeln!(out, "   ❌ LBA {}-{} (bytes {}-{}) {}",
+                    range.start / logical, (range.end - 1) / logical, range.start, range.end - 1, label)?;
+            }
+            if ranges.len() > MAX_LISTED_RANGES {
+                writeln!(out, "   ... and {} more {} ranges", ranges.len() - MAX_LISTED_RANGES, label)?;
+            }
+        }
+    }
+
+    if let Some(outcome) = &record.residue {
+        writeln!(out, "🔎 Residue scan: {}", outcome.describe())?;
+        for hit in &outcome.hits {
+            writeln!(out, "   ❌ {} at byte {} (LBA {})", hit.signature, hit.offset, hit.offset / record.geometry.logical)?;
+        }
+        if outcome.total_hits > outcome.hits.len() as u64 {
+            writeln!(out, "   ... and {} more hits", outcome.total_hits - outcome.hits.len() as u64)?;
+        }
+        if let Some(hpa) = &outcome.hidden_area {
+            writeln!(out, "   ⚠️  Host protected area: {} - sectors hidden from the OS were neither wiped nor scanned", hpa)?;
+        }
+    }
+
+    let header_survived = record.steps.iter()
+        .any(|step| step.name == STEP_HEADER_CHECK && matches!(step.status, StepStatus::Failed(_)));
+    let destroyed = (record.step_completed(STEP_FILL) && record.step_completed(STEP_DESTROY_KEYS) && !header_survived)
+        || record.step_completed(STEP_OVERWRITE);
+    if !destroyed {
+        writeln!(out, "🛑 Security: NOT WIPED - the wipe did not run to completion")?;
+    } else if record.defects.is_empty() {
+        match &record.pattern {
+            Some(pattern) => writeln!(out, "🛡️  Security: Every sector overwritten with {}", pattern.describe())?,
+            None => writeln!(out, "🛡️  Security: Data is cryptographically unrecoverable")?,
+        }
+    } else {
+        let logical = record.geometry.logical;
+        let bad: u64 = record.defects.iter().map(ByteRange::len).sum();
+        writeln!(out, "🛑 Security: INCOMPLETE - {} bytes could not be overwritten", bad)?;
+        writeln!(out, "🧱 Defect map (unwritable LBA ranges, {}-byte sectors):", logical)?;
+        for defect in &record.defects {
+            writeln!(out, "   LBA {}-{} ({} sectors)",
+                defect.start / logical,
+                (defect.end - 1) / logical,
+                defect.len().div_ceil(logical))?;
+        }
+        writeln!(out, "🔨 Recommendation: route this drive to physical destruction, not resale")?;
+    }
+    if !record.resumes.is_empty() {
+        writeln!(out, "⏸️  Interruptions: {}", record.resumes.len())?;
+        for resume in &record.resumes {
+            writeln!(out, "   Interrupted at {} (Unix timestamp) after {} bytes, resumed at {} {}",
+                resume.interrupted_at, resume.offset, resume.resumed_at,
+                if record.pattern.is_some() { "continuing the same pattern" } else { "with a fresh key" })?;
+        }
+    }
+    if let Some(provisioned) = &record.provisioned {
+        writeln!(out, "💿 Re-provisioned:")?;
+        if let Some(table) = provisioned.table {
+            writeln!(out, "   Partition table: {}", table.name())?;
+        }
+        writeln!(out, "   Partition: {}", provisioned.partition)?;
+        writeln!(out, "   Filesystem: {} (label '{}')", provisioned.filesystem.name(), provisioned.label)?;
+        if let Some(uuid) = &provisioned.fs_uuid {
+            writeln!(out, "   Filesystem UUID: {}", uuid)?;
+        }
+    }
+
+    writeln!(out, "🚚 Destination: {}", disposition.destination.map(|destination| destination.title()).unwrap_or("not recorded"))?;
+    writeln!(out, "👷 Sanitized by: {}", disposition.operator)?;
+    writeln!(out, "🧾 Validated by: {}", disposition.validator.as_deref().unwrap_or("not recorded"))?;
+    writeln!(out, "🕒 Started: {} (Unix timestamp)", record.started)?;
+    match record.finished {
+        Some(finished) => writeln!(out, "🕒 Completed: {} (Unix timestamp)", finished),
+        None => writeln!(out, "🕒 Aborted: {} (Unix timestamp)", unix_now()),
+    }?;
+    writeln!(out, "{}", separator)?;
+    if record.succeeded() {
+        writeln!(out, "\n🎉 Mission accomplished! Your data is gone forever! 🎉")?;
+    }
+    Ok(())
+}
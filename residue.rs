@@ -0,0 +1,258 @@
+use std::fs::OpenOptions;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::process::Command as ProcessCommand;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::geometry;
+use crate::verify::{self, SAMPLE_SIZE};
+use crate::write_engine::AlignedBuffer;
+
+// Read size of a full scan
+const FULL_SCAN_BLOCK: u64 = 8 * 1024 * 1024;
+// File systems place files, records and headers on 512-byte boundaries at
+// the least, so signatures are only looked for there
+const SCAN_STEP: usize = 512;
+const MAX_REPORTED_HITS: usize = 64;
+
+/// Magic bytes that mark the start of a file or on-disk structure. Every part
+/// must match; together they are at least 7 bytes long, so random data or
+/// ciphertext practically never matches, even over a whole drive (56 bits
+/// give one false hit in about 2^25 TiB scanned at 512-byte steps).
+struct Signature {
+    name: &'static str,
+    parts: &'static [(usize, &'static [u8])],
+}
+
+const SIGNATURES: &[Signature] = &[
+    Signature { name: "JPEG image (JFIF)", parts: &[(0, b"\xFF\xD8\xFF\xE0"), (6, b"JFIF\0")] },
+    Signature { name: "JPEG image (Exif)", parts: &[(0, b"\xFF\xD8\xFF\xE1"), (6, b"Exif\0")] },
+    Signature { name: "PNG image", parts: &[(0, b"\x89PNG\r\n\x1a\n")] },
+    // Header line "%PDF-1.x" ends in CR or LF; the version digit varies
+    Signature { name: "PDF document", parts: &[(0, b"%PDF-1."), (8, b"\n")] },
+    Signature { name: "PDF document", parts: &[(0, b"%PDF-1."), (8, b"\r")] },
+    Signature { name: "PDF document", parts: &[(0, b"%PDF-2.0")] },
+    // Also .docx/.xlsx/.pptx: local file header with a "version needed" high
+    // byte of 0 and the file deflated or stored
+    Signature { name: "ZIP / Office Open XML", parts: &[(0, b"PK\x03\x04"), (5, b"\0"), (8, b"\x08\0")] },
+    Signature { name: "ZIP / Office Open XML", parts: &[(0, b"PK\x03\x04"), (5, b"\0"), (8, b"\0\0")] },
+    Signature { name: "OLE2 / legacy Office document", parts: &[(0, b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1")] },
+    Signature { name: "SQLite database", parts: &[(0, b"SQLite format 3\0")] },
+    // "FILE", update sequence offset 0x30 and a small update sequence count
+    Signature { name: "NTFS MFT record", parts: &[(0, b"FILE0\0"), (7, b"\0")] },
+    Signature { name: "NTFS boot sector", parts: &[(3, b"NTFS    ")] },
+    // Magic 0xEF53 plus block size, creator OS and revision small enough to be real
+    Signature { name: "ext2/3/4 superblock", parts: &[(0x38, b"\x53\xEF"), (0x19, b"\0\0\0"), (0x49, b"\0\0\0"), (0x4D, b"\0\0\0")] },
+    // Magic plus the high byte of the 16-bit version (1 or 2)
+    Signature { name: "LUKS header", parts: &[(0, b"LUKS\xBA\xBE"), (6, b"\0")] },
+    Signature { name: "LUKS2 secondary header", parts: &[(0, b"SKUL\xBA\xBE"), (6, b"\0")] },
+    Signature { name: "BitLocker volume", parts: &[(3, b"-FVE-FS-")] },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanMode {
+    Sampled,
+    Full,
+}
+
+impl ScanMode {
+    pub fn parse(value: &str) -> io::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "sampled" | "sample" => Ok(ScanMode::Sampled),
+            "full" | "all" => Ok(ScanMode::Full),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown residue scan mode '{}' (expected sampled or full)", other)
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScanMode::Sampled => "sampled",
+            ScanMode::Full => "full",
+        }
+    }
+}
+
+/// A signature found on the wiped device, at a device offset.
+#[derive(Debug, Clone)]
+pub struct ResidueHit {
+    pub offset: u64,
+    pub signature: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResidueOutcome {
+    pub mode: ScanMode,
+    pub seed: u64,
+    pub target_size: u64,
+    pub bytes_scanned: u64,
+    pub bytes_unreadable: u64,
+    pub hits: Vec<ResidueHit>,
+    pub total_hits: u64,
+    /// hdparm's description of a host protected area, if one is set
+    pub hidden_area: Option<String>,
+}
+
+impl ResidueOutcome {
+    pub fn clean(&self) -> bool {
+        self.total_hits == 0
+    }
+
+    pub fn describe(&self) -> String {
+        let coverage = if self.target_size == 0 { 0.0 } else { self.bytes_scanned as f64 * 100.0 / self.target_size as f64 };
+        let mut summary = format!("{} scan of {} bytes ({:.3}% of the device) for {} signatures: {} hits",
+            self.mode.name(), self.bytes_scanned, coverage, signature_kinds(), self.total_hits);
+        if self.mode == ScanMode::Sampled {
+            summary.push_str(&format!(" (sample seed {})", self.seed));
+        }
+        if self.bytes_unreadable > 0 {
+            summary.push_str(&format!(", {} bytes unreadable", self.bytes_unreadable));
+        }
+        summary
+    }
+}
+
+/// Number of distinct file types looked for; some have several signatures.
+fn signature_kinds() -> usize {
+    SIGNATURES
+        .iter()
+        .enumerate()
+        .filter(|(i, signature)| SIGNATURES[..*i].iter().all(|other| other.name != signature.name))
+        .count()
+}
+
+fn match_signature(data: &[u8]) -> Option<&'static str> {
+    SIGNATURES
+        .iter()
+        .find(|signature| signature.parts.iter().all(|(offset, magic)| {
+            data.get(*offset..offset + magic.len()) == Some(*magic)
+        }))
+        .map(|signature| signature.name)
+}
+
+/// Size of the host protected area as reported by `hdparm -N`, e.g.
+/// "max sectors = 976771055/976773168, HPA is enabled". Sectors above the
+/// first number are invisible to the OS, so neither wiped nor scanned.
+pub fn hidden_area(device: &str) -> Option<String> {
+    let output = ProcessCommand::new("hdparm")
+        .args(["-N", device])
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("max sectors") && line.contains("HPA is enabled"))
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Search `target` for leftover file signatures: every block in full mode,
+/// otherwise stratified samples covering `coverage_percent` of it, chosen by
+/// `seed`. Hits are reported at `device_offset + offset`.
+pub fn scan(target: &str, mode: ScanMode, coverage_percent: f64, seed: u64, device_offset: u64) -> io::Result<ResidueOutcome> {
+    let mut device = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(target)?;
+    let size = device.seek(SeekFrom::End(0))?;
+    let geometry = geometry::read_geometry(target).unwrap_or_default();
+
+    let (block_size, offsets) = match mode {
+        ScanMode::Full => (FULL_SCAN_BLOCK, (0..size).step_by(FULL_SCAN_BLOCK as usize).collect::<Vec<_>>()),
+        ScanMode::Sampled => {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (SAMPLE_SIZE, verify::sample_offsets(size, coverage_percent, &mut rng))
+        }
+    };
+    let mut buffer = AlignedBuffer::zeroed(block_size as usize, geometry.logical as usize);
+    let mut outcome = ResidueOutcome {
+        mode,
+        seed,
+        target_size: size,
+        bytes_scanned: 0,
+        bytes_unreadable: 0,
+        hits: Vec::new(),
+        total_hits: 0,
+        hidden_area: None,
+    };
+
+    for (i, &offset) in offsets.iter().enumerate() {
+        let len = block_size.min(size - offset) as usize;
+        let data = &mut buffer.as_mut_slice()[..len];
+        match verify::read_sample(&device, data, offset) {
+            Ok(()) => outcome.bytes_scanned += len as u64,
+            // Unwritable sectors are already in the defect map
+            Err(e) if e.raw_os_error() == Some(libc::EIO) => {
+                outcome.bytes_unreadable += len as u64;
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+
+        for position in (0..len).step_by(SCAN_STEP) {
+            if let Some(signature) = match_signature(&data[position..]) {
+                outcome.total_hits += 1;
+                if outcome.hits.len() < MAX_REPORTED_HITS {
+                    outcome.hits.push(ResidueHit { offset: device_offset + offset + position as u64, signature });
+                }
+            }
+        }
+
+        if i % 16 == 0 || i + 1 == offsets.len() {
+            print!("\r   Scanned {} / {} blocks ({} hits)   ", i + 1, offsets.len(), outcome.total_hits);
+            io::stdout().flush()?;
+        }
+    }
+    println!();
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+    use rand_chacha::ChaCha20Rng;
+
+    fn sector(header: &[u8]) -> Vec<u8> {
+        let mut data = vec![0x55u8; SCAN_STEP];
+        data[..header.len()].copy_from_slice(header);
+        data
+    }
+
+    #[test]
+    fn every_signature_has_enough_magic() {
+        for signature in SIGNATURES {
+            let bytes: usize = signature.parts.iter().map(|(_, magic)| magic.len()).sum();
+            assert!(bytes >= 7, "{} has only {} bytes of magic", signature.name, bytes);
+        }
+        assert_eq!(signature_kinds(), 13);
+    }
+
+    #[test]
+    fn real_headers_match() {
+        let headers: &[(&[u8], &str)] = &[
+            (b"%PDF-1.4\n%\xE2\xE3\xCF\xD3", "PDF document"),
+            (b"%PDF-1.7\r\n", "PDF document"),
+            (b"%PDF-2.0\n", "PDF document"),
+            (b"PK\x03\x04\x14\0\x06\0\x08\0", "ZIP / Office Open XML"),
+            (b"PK\x03\x04\x0A\0\0\0\0\0", "ZIP / Office Open XML"),
+            (b"FILE0\0\x03\0", "NTFS MFT record"),
+            (b"LUKS\xBA\xBE\0\x02", "LUKS header"),
+        ];
+        for (header, name) in headers {
+            assert_eq!(match_signature(&sector(header)), Some(*name), "{:?}", header);
+        }
+        // The old 5-byte prefix alone no longer counts
+        assert_eq!(match_signature(&sector(b"%PDF-x")), None);
+        assert_eq!(match_signature(&sector(b"PK\x03\x04\x14\0\x06\0\x63\x01")), None);
+    }
+
+    #[test]
+    fn random_data_has_no_hits() {
+        let mut data = vec![0u8; 16 << 20];
+        ChaCha20Rng::from_seed([9u8; 32]).fill_bytes(&mut data);
+        assert!(data.chunks(SCAN_STEP).all(|chunk| match_signature(chunk).is_none()));
+    }
+}
@@ -0,0 +1,144 @@
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::io::{self, Write};
+use std::process::{Command, Output, Stdio};
+use zeroize::Zeroize;
+use crate::entropy::EntropySource;
+
+/// Raw key bytes handed to cryptsetup as the LUKS passphrase.
+pub const KEY_BYTES: usize = 64;
+
+/// Key material from the health-tested kernel CSPRNG in its own page-aligned allocation,
+/// locked into RAM (never swapped), excluded from core dumps and zeroed on
+/// drop. It cannot be printed: `Debug` shows only the length.
+pub struct SecretKey {
+    ptr: *mut u8,
+    layout: Layout,
+    len: usize,
+    locked: bool,
+}
+
+fn page_size() -> usize {
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 { size as usize } else { 4096 }
+}
+
+impl SecretKey {
+    pub fn generate(len: usize, source: &mut EntropySource) -> io::Result<Self> {
+        let page = page_size();
+        let layout = Layout::from_size_align(len.div_ceil(page).max(1) * page, page)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            return Err(io::Error::new(io::ErrorKind::OutOfMemory, "Could not allocate key memory"));
+        }
+
+        let locked = unsafe { libc::mlock(ptr as *const libc::c_void, layout.size()) } == 0;
+        if !locked {
+            println!("⚠️  Could not lock key memory (RLIMIT_MEMLOCK?) - it may be swapped out");
+        }
+        unsafe { libc::madvise(ptr as *mut libc::c_void, layout.size(), libc::MADV_DONTDUMP) };
+
+        let mut key = SecretKey { ptr, layout, len, locked };
+        source.fill(key.as_mut_bytes())?;
+        Ok(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    fn as_mut_bytes(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        let whole = unsafe { std::slice::from_raw_parts_mut(self.ptr, self.layout.size()) };
+        whole.zeroize();
+        unsafe {
+            if self.locked {
+                libc::munlock(self.ptr as *const libc::c_void, self.layout.size());
+            }
+            alloc::dealloc(self.ptr, self.layout);
+        }
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey([REDACTED; {} bytes])", self.len)
+    }
+}
+
+/// Run `command` with the key written to its stdin through an anonymous
+/// pipe; the command must read it with `--key-file=- --keyfile-size N`.
+/// The key never touches argv, the environment or the filesystem.
+pub fn run_with_key(command: &mut Command, key: &SecretKey) -> io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(key.as_bytes())?;
+        // Dropping stdin closes the pipe so the child sees EOF
+    }
+    child.wait_with_output()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cipher::CryptParams;
+    use crate::geometry::SectorGeometry;
+    use crate::identity::DeviceIdentity;
+    use crate::report::{self, WipeRecord};
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|window| window == needle)
+    }
+
+    #[test]
+    fn secret_never_appears_in_output_or_reports() {
+        let mut source = EntropySource::start().unwrap();
+        let key = SecretKey::generate(KEY_BYTES, &mut source).unwrap();
+        assert_eq!(key.len(), KEY_BYTES);
+        assert!(key.as_bytes().iter().any(|&b| b != 0));
+        let encodings = [key.as_bytes().to_vec(), hex::encode(key.as_bytes()).into_bytes()];
+
+        // Debug formatting is redacted
+        let debug = format!("{:?}", key);
+        assert_eq!(debug, format!("SecretKey([REDACTED; {} bytes])", KEY_BYTES));
+
+        // The key reaches the child through the pipe and nowhere else
+        let output = run_with_key(Command::new("sh").args(["-c", "wc -c"]), &key).unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), KEY_BYTES.to_string());
+
+        // A report of a wipe run with this key does not contain it
+        let identity = DeviceIdentity { model: "Test".into(), serial: "0".into(), size_bytes: 1 << 30 };
+        let mut record = WipeRecord::new("test", "/dev/null", &identity, "luks2-crypto-fill", SectorGeometry::default());
+        record.run_step("Generate key", || Ok(&key)).unwrap();
+        record.detail("Key", format!("{:?}", key));
+        record.encryption = Some(CryptParams { mode: "LUKS2".into(), cipher: "aes-xts-plain64".into(), ..Default::default() });
+        let mut rendered = Vec::new();
+        report::write_report(&record, &mut rendered).unwrap();
+
+        for encoding in &encodings {
+            assert!(!contains(&rendered, encoding));
+            assert!(!contains(debug.as_bytes(), encoding));
+            assert!(!contains(&output.stdout, encoding));
+            assert!(!contains(&output.stderr, encoding));
+        }
+    }
+}
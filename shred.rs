@@ -0,0 +1,343 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use rand::{thread_rng, Rng, RngCore};
+use rand_chacha::ChaCha20Rng;
+use crate::entropy::EntropySource;
+
+// FS_IOC_FIEMAP = _IOWR('f', 11, struct fiemap)
+const FS_IOC_FIEMAP: libc::c_ulong = 0xC020_660B;
+const FIEMAP_FLAG_SYNC: u32 = 0x0001;
+const FIEMAP_EXTENT_LAST: u32 = 0x0001;
+const FIEMAP_EXTENT_UNKNOWN: u32 = 0x0002;
+const FIEMAP_EXTENT_DATA_INLINE: u32 = 0x0200;
+const FIEMAP_EXTENT_UNWRITTEN: u32 = 0x0800;
+const FIEMAP_EXTENT_SHARED: u32 = 0x2000;
+const EXTENTS_PER_CALL: usize = 128;
+
+// Filesystems that never overwrite a block in place (copy-on-write or log structured)
+const COW_FILESYSTEMS: &[(i64, &str)] = &[
+    (0x9123_683E, "btrfs"),
+    (0x2FC1_2FC1, "zfs"),
+    (0xF2F5_2010, "f2fs"),
+    (0xCA45_1A4E, "bcachefs"),
+    (0x3434, "nilfs2"),
+];
+
+const RENAME_ROUNDS: usize = 7;
+const WRITE_CHUNK: usize = 1024 * 1024;
+
+#[repr(C)]
+#[derive(Default)]
+struct FiemapHeader {
+    fm_start: u64,
+    fm_length: u64,
+    fm_flags: u32,
+    fm_mapped_extents: u32,
+    fm_extent_count: u32,
+    fm_reserved: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct FiemapExtent {
+    fe_logical: u64,
+    fe_physical: u64,
+    fe_length: u64,
+    fe_reserved64: [u64; 2],
+    fe_flags: u32,
+    fe_reserved: [u32; 3],
+}
+
+#[repr(C)]
+struct FiemapRequest {
+    header: FiemapHeader,
+    extents: [FiemapExtent; EXTENTS_PER_CALL],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Extent {
+    logical: u64,
+    physical: u64,
+    length: u64,
+    flags: u32,
+}
+
+#[derive(Default)]
+pub struct ShredReport {
+    pub files_shredded: usize,
+    pub directories_removed: usize,
+    pub bytes_overwritten: u64,
+    pub extents_overwritten: usize,
+    pub entropy: String,
+    pub warnings: Vec<String>,
+}
+
+/// Look up the physical extents backing a file via FIEMAP.
+fn file_extents(file: &File) -> io::Result<Vec<Extent>> {
+    let mut extents = Vec::new();
+    let mut start = 0u64;
+
+    loop {
+        let mut request = FiemapRequest {
+            header: FiemapHeader {
+                fm_start: start,
+                fm_length: u64::MAX - start,
+                fm_flags: FIEMAP_FLAG_SYNC,
+                fm_extent_count: EXTENTS_PER_CALL as u32,
+                ..Default::default()
+            },
+            extents: [FiemapExtent::default(); EXTENTS_PER_CALL],
+        };
+
+        let ret = unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_FIEMAP, &mut request) };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mapped = request.header.fm_mapped_extents as usize;
+        if mapped == 0 {
+            break;
+        }
+
+        let mut last = false;
+        for raw in &request.extents[..mapped] {
+            extents.push(Extent {
+                logical: raw.fe_logical,
+                physical: raw.fe_physical,
+                length: raw.fe_length,
+                flags: raw.fe_flags,
+            });
+            start = raw.fe_logical + raw.fe_length;
+            last |= raw.fe_flags & FIEMAP_EXTENT_LAST != 0;
+        }
+
+        if last {
+            break;
+        }
+    }
+
+    Ok(extents)
+}
+
+fn cow_filesystem(file: &File) -> Option<&'static str> {
+    let mut stats: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::fstatfs(file.as_raw_fd(), &mut stats) } != 0 {
+        return None;
+    }
+    let fs_type = stats.f_type as i64;
+    COW_FILESYSTEMS
+        .iter()
+        .find(|(magic, _)| *magic == fs_type)
+        .map(|(_, name)| *name)
+}
+
+fn random_name(len: usize) -> String {
+    let charset = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = thread_rng();
+    (0..len.max(1))
+        .map(|_| charset[rng.gen_range(0..charset.len())] as char)
+        .collect()
+}
+
+fn sync_parent(path: &Path) {
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+/// Rename an entry to random names of the same length several times so the
+/// original name is overwritten in the directory, returning the final path.
+fn scramble_name(path: &Path) -> io::Result<PathBuf> {
+    let name_len = path.file_name().map(|n| n.len()).unwrap_or(8);
+    let mut current = path.to_path_buf();
+
+    for _ in 0..RENAME_ROUNDS {
+        let candidate = current.with_file_name(random_name(name_len));
+        if candidate.exists() {
+            continue;
+        }
+        fs::rename(&current, &candidate)?;
+        sync_parent(&candidate);
+        current = candidate;
+    }
+
+    Ok(current)
+}
+
+fn overwrite_extents(file: &File, extents: &[Extent], file_size: u64, rng: &mut ChaCha20Rng, report: &mut ShredReport) -> io::Result<()> {
+    let mut buffer = vec![0u8; WRITE_CHUNK];
+
+    for extent in extents {
+        if extent.flags & (FIEMAP_EXTENT_UNWRITTEN | FIEMAP_EXTENT_UNKNOWN) != 0 {
+            continue;
+        }
+
+        // Stay inside the file's current size so the overwrite never extends it
+        let end = (extent.logical + extent.length).min(file_size.max(extent.logical));
+        let mut offset = extent.logical;
+        while offset < end {
+            let len = ((end - offset) as usize).min(WRITE_CHUNK);
+            rng.fill_bytes(&mut buffer[..len]);
+            file.write_all_at(&buffer[..len], offset)?;
+            offset += len as u64;
+            report.bytes_overwritten += len as u64;
+        }
+        report.extents_overwritten += 1;
+    }
+
+    file.sync_all()
+}
+
+fn shred_file(path: &Path, rng: &mut ChaCha20Rng, report: &mut ShredReport) -> io::Result<()> {
+    println!("🗑️  Shredding {}", path.display());
+
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    let file_size = file.metadata()?.len();
+
+    if let Some(fs_name) = cow_filesystem(&file) {
+        let warning = format!(
+            "{}: on {} (copy-on-write) - in-place overwrite is NOT guaranteed, old blocks may survive",
+            path.display(), fs_name
+        );
+        println!("⚠️  {}", warning);
+        report.warnings.push(warning);
+    }
+
+    let extents = match file_extents(&file) {
+        Ok(extents) => extents,
+        Err(e) => {
+            let warning = format!(
+                "{}: FIEMAP unavailable ({}) - overwriting by logical offset only",
+                path.display(), e
+            );
+            println!("⚠️  {}", warning);
+            report.warnings.push(warning);
+            vec![Extent { logical: 0, physical: 0, length: file_size, flags: 0 }]
+        }
+    };
+
+    if extents.iter().any(|e| e.flags & FIEMAP_EXTENT_SHARED != 0) {
+        let warning = format!(
+            "{}: shares extents with another file or snapshot - shared copies are not overwritten",
+            path.display()
+        );
+        println!("⚠️  {}", warning);
+        report.warnings.push(warning);
+    }
+    if extents.iter().any(|e| e.flags & FIEMAP_EXTENT_DATA_INLINE != 0) {
+        let warning = format!(
+            "{}: data stored inline in metadata - it is overwritten in place only if the filesystem allows it",
+            path.display()
+        );
+        println!("⚠️  {}", warning);
+        report.warnings.push(warning);
+    }
+
+    overwrite_extents(&file, &extents, file_size, rng, report)?;
+
+    // If the filesystem moved the data instead of overwriting it, the
+    // original physical blocks still hold the old contents
+    if let Ok(after) = file_extents(&file) {
+        let moved = extents.iter().zip(after.iter()).any(|(before, now)| {
+            before.physical != 0 && before.physical != now.physical
+        }) || extents.len() != after.len();
+        if moved {
+            let warning = format!(
+                "{}: filesystem relocated extents during overwrite - original blocks were not overwritten in place",
+                path.display()
+            );
+            println!("⚠️  {}", warning);
+            report.warnings.push(warning);
+        }
+    }
+
+    file.set_len(0)?;
+    file.sync_all()?;
+    drop(file);
+
+    let final_path = scramble_name(path)?;
+    fs::remove_file(&final_path)?;
+    sync_parent(&final_path);
+
+    report.files_shredded += 1;
+    Ok(())
+}
+
+fn shred_entry(path: &Path, rng: &mut ChaCha20Rng, report: &mut ShredReport) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    let file_type = metadata.file_type();
+
+    if file_type.is_dir() {
+        for entry in fs::read_dir(path)? {
+            shred_entry(&entry?.path(), rng, report)?;
+        }
+        let final_path = scramble_name(path)?;
+        fs::remove_dir(&final_path)?;
+        sync_parent(&final_path);
+        report.directories_removed += 1;
+    } else if file_type.is_file() {
+        shred_file(path, rng, report)?;
+    } else {
+        // Symlinks, sockets, fifos and device nodes carry no data of their own
+        println!("🔗 Removing {} (no data to overwrite)", path.display());
+        let final_path = scramble_name(path)?;
+        fs::remove_file(&final_path)?;
+        sync_parent(&final_path);
+    }
+
+    Ok(())
+}
+
+/// Securely delete files and directory trees: overwrite every file extent in
+/// place with random data, scramble the names, truncate and unlink.
+pub fn shred_paths(paths: &[String]) -> io::Result<ShredReport> {
+    let mut report = ShredReport::default();
+
+    println!("🎲 Checking the kernel random source...");
+    let mut entropy = EntropySource::start()?;
+    let mut rng = entropy.stream()?;
+    report.entropy = entropy.status().describe();
+
+    for path in paths {
+        let path = Path::new(path);
+        if fs::symlink_metadata(path).is_err() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} does not exist", path.display())
+            ));
+        }
+        shred_entry(path, &mut rng, &mut report)?;
+    }
+
+    io::stdout().flush()?;
+    Ok(report)
+}
+
+pub fn print_shred_report(report: &ShredReport) {
+    let separator = "═".repeat(60);
+    println!("\n{}", separator);
+    println!("📋 SHRED COMPLETION REPORT");
+    println!("{}", separator);
+    println!("🗑️  Files shredded: {}", report.files_shredded);
+    println!("📂 Directories removed: {}", report.directories_removed);
+    println!("🧩 Extents overwritten: {}", report.extents_overwritten);
+    println!("📏 Bytes overwritten: {}", report.bytes_overwritten);
+    println!("🎲 Overwrite data: ChaCha20 stream from a health-tested seed");
+    println!("🎲 Entropy: {}", report.entropy);
+    println!("🔄 Names scrambled {} times before unlink", RENAME_ROUNDS);
+    if report.warnings.is_empty() {
+        println!("🛡️  All extents overwritten in place");
+    } else {
+        println!("⚠️  Warnings:");
+        for warning in &report.warnings {
+            println!("   - {}", warning);
+        }
+    }
+    println!("{}", separator);
+}
@@ -0,0 +1,242 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+pub const DEFAULT_STATION_KEY: &str = "/var/lib/wipeshit/station.key";
+pub const ALGORITHM: &str = "Ed25519";
+
+/// Ed25519 key of this wipe station. The 32-byte secret seed is kept in a
+/// root-only file (hex encoded) and generated on first use.
+pub struct StationKey {
+    key: SigningKey,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// "SHA256:<hex>" of the raw public key, short enough to compare by eye and
+/// to publish for buyers.
+pub fn fingerprint(public_key: &VerifyingKey) -> String {
+    format!("SHA256:{}", hex::encode(Sha256::digest(public_key.as_bytes())))
+}
+
+impl StationKey {
+    pub fn load_or_create(path: &str) -> io::Result<Self> {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::create(path),
+            Err(e) => return Err(e),
+        };
+
+        // Like ssh, refuse a private key that others can read: anyone who
+        // could copy it can sign certificates in this station's name
+        let mode = file.metadata()?.permissions().mode();
+        if mode & 0o077 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Station key {} is accessible by other users (mode {:04o}); run 'chmod 600 {}' \
+                         and consider the key compromised", path, mode & 0o7777, path)
+            ));
+        }
+
+        // Decoded straight into the seed array so no copy is left behind
+        let mut content = String::new();
+        let mut seed = [0u8; 32];
+        let decoded = file.read_to_string(&mut content)
+            .map(|_| hex::decode_to_slice(content.trim(), &mut seed).is_ok());
+        content.zeroize();
+        if !decoded? {
+            seed.zeroize();
+            return Err(invalid(format!("Station key {} is not a hex-encoded 32-byte seed", path)));
+        }
+        let key = SigningKey::from_bytes(&seed);
+        seed.zeroize();
+        Ok(StationKey { key })
+    }
+
+    fn create(path: &str) -> io::Result<Self> {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut seed = [0u8; 32];
+        getrandom::getrandom(&mut seed).map_err(|e| io::Error::other(
+            format!("Kernel CSPRNG unavailable: {}", e)
+        ))?;
+        let key = SigningKey::from_bytes(&seed);
+        let mut encoded = hex::encode(seed);
+        seed.zeroize();
+
+        // Written in full under a private name, then linked into place: parallel
+        // jobs never see a half-written key, and the first one to link wins
+        let staging = format!("{}.{}.new", path, &hex::encode(key.verifying_key().as_bytes())[..16]);
+        let written = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&staging)
+            .and_then(|mut file| file.write_all(encoded.as_bytes()).and_then(|_| file.sync_all()));
+        encoded.zeroize();
+        let linked = written.and_then(|_| fs::hard_link(&staging, path));
+        let _ = fs::remove_file(&staging);
+        match linked {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Self::load_or_create(path),
+            Err(e) => return Err(e),
+        }
+
+        let station = StationKey { key };
+        println!("🔑 Created station signing key {} ({})", path, station.fingerprint());
+        Ok(station)
+    }
+
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.key.verifying_key())
+    }
+
+    /// Sign `document` and add the signature as its "signature" member.
+    pub fn sign(&self, document: &mut Value) -> io::Result<()> {
+        let signature = self.key.sign(&canonical_bytes(document)?);
+        let public_key = self.key.verifying_key();
+        document["signature"] = json!({
+            "algorithm": ALGORITHM,
+            "public_key": hex::encode(public_key.as_bytes()),
+            "key_fingerprint": fingerprint(&public_key),
+            "value": hex::encode(signature.to_bytes()),
+        });
+        Ok(())
+    }
+}
+
+/// Bytes that are signed: the document without its "signature" member as
+/// compact JSON with object keys sorted (serde_json's default map order), so
+/// pretty-printing or reordering the file does not break the signature but
+/// changing any value does.
+fn canonical_bytes(document: &Value) -> io::Result<Vec<u8>> {
+    let mut unsigned = document.clone();
+    if let Some(object) = unsigned.as_object_mut() {
+        object.remove("signature");
+    }
+    serde_json::to_vec(&unsigned).map_err(|e| io::Error::other(e.to_string()))
+}
+
+/// Fingerprints from a trusted-keys file: one "SHA256:<hex>" per line,
+/// blank lines and `#` comments ignored.
+pub fn read_trusted_keys(path: &str) -> io::Result<Vec<String>> {
+    let mut trusted = Vec::new();
+    for line in fs::read_to_string(path)?.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        if !line.starts_with("SHA256:") {
+            return Err(invalid(format!("{}: '{}' is not a SHA256:<hex> key fingerprint", path, line)));
+        }
+        trusted.push(line.to_string());
+    }
+    if trusted.is_empty() {
+        return Err(invalid(format!("{} lists no trusted keys", path)));
+    }
+    Ok(trusted)
+}
+
+/// Outcome of checking a signed document.
+pub struct Verified {
+    pub key_fingerprint: String,
+}
+
+/// Check the signature of a signed document with the public key embedded in
+/// it. Whether that key belongs to a trusted station is up to the caller,
+/// by comparing the returned fingerprint.
+pub fn verify(document: &Value) -> io::Result<Verified> {
+    let signature = document.get("signature").ok_or_else(|| invalid("Certificate is not signed".to_string()))?;
+    let field = |name: &str| -> io::Result<Vec<u8>> {
+        signature.get(name)
+            .and_then(Value::as_str)
+            .and_then(|value| hex::decode(value).ok())
+            .ok_or_else(|| invalid(format!("Signature field '{}' is missing or not hex", name)))
+    };
+
+    if signature.get("algorithm").and_then(Value::as_str) != Some(ALGORITHM) {
+        return Err(invalid(format!("Unsupported signature algorithm (expected {})", ALGORITHM)));
+    }
+    let public_key: [u8; 32] = field("public_key")?.try_into().map_err(|_| invalid("Public key is not 32 bytes".to_string()))?;
+    let public_key = VerifyingKey::from_bytes(&public_key).map_err(|e| invalid(format!("Invalid public key: {}", e)))?;
+    let value: [u8; 64] = field("value")?.try_into().map_err(|_| invalid("Signature is not 64 bytes".to_string()))?;
+
+    let key_fingerprint = fingerprint(&public_key);
+    if signature.get("key_fingerprint").and_then(Value::as_str) != Some(key_fingerprint.as_str()) {
+        return Err(invalid("Key fingerprint does not match the embedded public key".to_string()));
+    }
+    public_key
+        .verify_strict(&canonical_bytes(document)?, &Signature::from_bytes(&value))
+        .map_err(|_| invalid("Signature does not match - the certificate was modified or signed with another key".to_string()))?;
+
+    Ok(Verified { key_fingerprint })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_detects_tampering() {
+        let station = StationKey { key: SigningKey::from_bytes(&[7u8; 32]) };
+        let mut document = json!({ "operation_id": "test", "result": { "status": "wiped", "bytes_written": 4096 } });
+        station.sign(&mut document).unwrap();
+
+        // Reformatting keeps the signature valid
+        let reparsed: Value = serde_json::from_str(&serde_json::to_string_pretty(&document).unwrap()).unwrap();
+        assert_eq!(verify(&reparsed).unwrap().key_fingerprint, station.fingerprint());
+
+        let mut tampered = document.clone();
+        tampered["result"]["status"] = json!("failed");
+        assert!(verify(&tampered).is_err());
+
+        // A different key substituted together with its fingerprint still fails
+        let other = StationKey { key: SigningKey::from_bytes(&[9u8; 32]) };
+        let mut forged = document.clone();
+        forged["signature"]["public_key"] = json!(hex::encode(other.key.verifying_key().as_bytes()));
+        forged["signature"]["key_fingerprint"] = json!(other.fingerprint());
+        assert!(verify(&forged).is_err());
+    }
+
+    #[test]
+    fn key_file_must_be_private() {
+        let dir = std::env::temp_dir().join(format!("wipeshit-key-{}", std::process::id()));
+        let path = dir.join("station.key");
+        let path = path.to_str().unwrap();
+        let created = StationKey::load_or_create(path).unwrap();
+        assert_eq!(fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(StationKey::load_or_create(path).unwrap().fingerprint(), created.fingerprint());
+
+        fs::set_permissions(path, fs::Permissions::from_mode(0o640)).unwrap();
+        let error = StationKey::load_or_create(path).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+
+        // A seed of the wrong length is rejected, not truncated or padded
+        fs::write(path, "ab".repeat(31)).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(StationKey::load_or_create(path).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn trusted_keys_file_lists_fingerprints() {
+        let path = std::env::temp_dir().join(format!("wipeshit-trusted-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "# Station A\nSHA256:00ff  # bench 1\n\n  SHA256:abcd\n").unwrap();
+        assert_eq!(read_trusted_keys(path).unwrap(), ["SHA256:00ff", "SHA256:abcd"]);
+
+        fs::write(path, "00ff\n").unwrap();
+        assert!(read_trusted_keys(path).is_err());
+        fs::write(path, "# nothing yet\n").unwrap();
+        assert!(read_trusted_keys(path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BUS_REGISTRY_DIR: &str = "/run/wipeshit/bus";
+const SHARE_REFRESH: Duration = Duration::from_secs(2);
+const BYTES_PER_MB: u64 = 1_000_000;
+
+/// USB bus or storage controller a block device hangs off, derived from its
+/// sysfs path, e.g. `usb2` or `pci0000:00/0000:00:17.0`. Devices with the
+/// same key share the same upstream bandwidth.
+pub fn bus_key(device: &str) -> Option<String> {
+    let device_name = device.strip_prefix("/dev/").unwrap_or(device);
+    let mut sys_path = fs::canonicalize(format!("/sys/class/block/{}", device_name)).ok()?;
+    if sys_path.join("partition").exists() {
+        sys_path.pop();
+    }
+
+    let components: Vec<String> = sys_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let devices_at = components.iter().position(|c| c == "devices")?;
+    let path = &components[devices_at + 1..];
+
+    // Everything on one USB root hub shares the host controller's bandwidth
+    if let Some(usb) = path.iter().position(|c| is_usb_bus(c)) {
+        return Some(path[..=usb].join("/"));
+    }
+
+    // Otherwise group by the last PCI function (the storage controller)
+    let controller = path.iter().rposition(|c| is_pci_address(c))?;
+    Some(path[..=controller].join("/"))
+}
+
+fn is_usb_bus(component: &str) -> bool {
+    component.strip_prefix("usb").is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn is_pci_address(component: &str) -> bool {
+    // dddd:bb:dd.f
+    let bytes = component.as_bytes();
+    bytes.len() == 12 && bytes[4] == b':' && bytes[7] == b':' && bytes[10] == b'.'
+}
+
+/// Registration of this process on a bus so concurrent jobs on the same bus
+/// (in this or another wipeshit process) can split the bus limit between them.
+struct BusShare {
+    limit: u64,
+    dir: PathBuf,
+    registration: PathBuf,
+}
+
+impl BusShare {
+    fn register(key: &str, limit: u64) -> io::Result<Self> {
+        let sanitized: String = key
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let dir = Path::new(BUS_REGISTRY_DIR).join(sanitized);
+        fs::create_dir_all(&dir)?;
+        let registration = dir.join(std::process::id().to_string());
+        fs::write(&registration, key)?;
+        Ok(BusShare { limit, dir, registration })
+    }
+
+    fn active_jobs(&self) -> u64 {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return 1,
+        };
+        let mut active = 0;
+        for entry in entries.flatten() {
+            let pid = entry.file_name().to_string_lossy().to_string();
+            if Path::new(&format!("/proc/{}", pid)).exists() {
+                active += 1;
+            } else {
+                // Left behind by a job that crashed
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+        active.max(1)
+    }
+
+    fn share(&self) -> u64 {
+        self.limit / self.active_jobs()
+    }
+}
+
+impl Drop for BusShare {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.registration);
+    }
+}
+
+/// Paces writes so a job stays under its own limit and under its fair share
+/// of the limit for its bus. Both limits are in bytes per second.
+pub struct Throttle {
+    job_limit: Option<u64>,
+    bus: Option<BusShare>,
+    rate: Option<u64>,
+    window_start: Instant,
+    window_bytes: u64,
+    last_refresh: Instant,
+}
+
+impl Throttle {
+    fn unlimited() -> Self {
+        Throttle {
+            job_limit: None,
+            bus: None,
+            rate: None,
+            window_start: Instant::now(),
+            window_bytes: 0,
+            last_refresh: Instant::now(),
+        }
+    }
+
+    /// Limits are given in MB/s as on the command line.
+    pub fn new(device: &str, job_mbps: Option<u64>, bus_mbps: Option<u64>) -> Self {
+        let mut throttle = Throttle::unlimited();
+        throttle.job_limit = job_mbps.map(|mbps| mbps * BYTES_PER_MB);
+
+        if let Some(mbps) = bus_mbps {
+            match bus_key(device) {
+                Some(key) => match BusShare::register(&key, mbps * BYTES_PER_MB) {
+                    Ok(share) => {
+                        println!("🚦 Bus {} limited to {} MB/s shared between its jobs", key, mbps);
+                        throttle.bus = Some(share);
+                    }
+                    Err(e) => println!("⚠️  Could not register with {} ({}) - bus limit NOT applied", BUS_REGISTRY_DIR, e),
+                },
+                None => println!("⚠️  Could not determine the bus of {} - bus limit NOT applied", device),
+            }
+        }
+        if let Some(mbps) = job_mbps {
+            println!("🚦 Job limited to {} MB/s", mbps);
+        }
+
+        throttle.rate = throttle.current_rate();
+        throttle
+    }
+
+    fn current_rate(&self) -> Option<u64> {
+        match (self.job_limit, self.bus.as_ref().map(BusShare::share)) {
+            (Some(job), Some(bus)) => Some(job.min(bus)),
+            (job, bus) => job.or(bus),
+        }
+    }
+
+    /// Account for `bytes` just written and sleep long enough to keep the
+    /// average rate since the start of the window under the limit.
+    pub fn pace(&mut self, bytes: usize) {
+        if self.job_limit.is_none() && self.bus.is_none() {
+            return;
+        }
+
+        // Other jobs on the bus come and go; re-read our share regularly and
+        // start a new window so the new rate takes effect immediately
+        if self.bus.is_some() && self.last_refresh.elapsed() >= SHARE_REFRESH {
+            let rate = self.current_rate();
+            if rate != self.rate {
+                self.rate = rate;
+                self.window_start = Instant::now();
+                self.window_bytes = 0;
+            }
+            self.last_refresh = Instant::now();
+        }
+
+        self.window_bytes += bytes as u64;
+        let rate = match self.rate {
+            Some(rate) if rate > 0 => rate,
+            _ => return,
+        };
+        let expected = Duration::from_secs_f64(self.window_bytes as f64 / rate as f64);
+        let elapsed = self.window_start.elapsed();
+        if expected > elapsed {
+            thread::sleep(expected - elapsed);
+        }
+    }
+}
@@ -0,0 +1,480 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+use std::time::{Duration, Instant};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::geometry;
+use crate::pattern::Pattern;
+use crate::range::ByteRange;
+use crate::write_engine::AlignedBuffer;
+
+/// Percentage of the device read back when no coverage is given.
+pub const DEFAULT_COVERAGE: f64 = 1.0;
+pub const SAMPLE_SIZE: u64 = 1024 * 1024;
+// Small devices are still sampled at enough places to mean something
+const MIN_SAMPLES: u64 = 128;
+// Shorter stretches of random data are too small for the statistics
+pub const MIN_RANDOM_SEGMENT: usize = 64 * 1024;
+// Chi-square over the byte histogram has 255 degrees of freedom; 415 is
+// the p = 1e-9 critical value, so a healthy wipe practically never fails
+const CHI_SQUARE_LIMIT: f64 = 415.0;
+const MIN_ENTROPY_BITS: f64 = 7.99;
+const MAX_REPORTED_FAILURES: usize = 32;
+// Read size of the full verification pass, the same as the write engine's
+const FULL_VERIFY_BLOCK: u64 = 8 * 1024 * 1024;
+
+/// What a region of the target should contain after the wipe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expected {
+    /// Ciphertext or random data: checked statistically
+    Random,
+    /// Every byte equal to the given value: checked exactly
+    Fill(u8),
+}
+
+impl Expected {
+    pub fn describe(&self) -> String {
+        match self {
+            Expected::Random => "random".to_string(),
+            Expected::Fill(byte) => format!("0x{:02x} fill", byte),
+        }
+    }
+}
+
+/// Where to expect what, in target offsets. `device_offset` is added to
+/// reported offsets so they refer to the physical device.
+#[derive(Debug, Clone)]
+pub struct VerifyPlan {
+    pub regions: Vec<(ByteRange, Expected)>,
+    pub skip: Vec<ByteRange>,
+    pub coverage_percent: f64,
+    pub device_offset: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SampleFailure {
+    pub offset: u64,
+    pub expected: Expected,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifyOutcome {
+    pub seed: u64,
+    pub samples: u64,
+    pub skipped_samples: u64,
+    pub bytes_read: u64,
+    pub target_size: u64,
+    pub min_entropy: Option<f64>,
+    pub max_chi_square: Option<f64>,
+    pub failures: Vec<SampleFailure>,
+    pub failed_samples: u64,
+}
+
+impl VerifyOutcome {
+    pub fn passed(&self) -> bool {
+        self.failed_samples == 0
+    }
+
+    pub fn coverage(&self) -> f64 {
+        if self.target_size == 0 { 0.0 } else { self.bytes_read as f64 * 100.0 / self.target_size as f64 }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut summary = format!("{} of {} samples failed, {:.3}% of the device read ({} bytes, sample seed {})",
+            self.failed_samples, self.samples, self.coverage(), self.bytes_read, self.seed);
+        if self.skipped_samples > 0 {
+            summary.push_str(&format!(", {} samples on unwritable sectors skipped", self.skipped_samples));
+        }
+        if let (Some(entropy), Some(chi_square)) = (self.min_entropy, self.max_chi_square) {
+            summary.push_str(&format!("; lowest entropy {:.4} bits/byte, highest chi-square {:.1}", entropy, chi_square));
+        }
+        summary
+    }
+}
+
+fn shannon_entropy(histogram: &[u64; 256], total: u64) -> f64 {
+    histogram
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn chi_square(histogram: &[u64; 256], total: u64) -> f64 {
+    let expected = total as f64 / 256.0;
+    histogram.iter().map(|&count| (count as f64 - expected).powi(2) / expected).sum()
+}
+
+/// Shannon entropy (bits/byte) and chi-square of `segment`'s byte histogram,
+/// and whether both are what random data or ciphertext gives.
+pub fn randomness(segment: &[u8]) -> (f64, f64, bool) {
+    let mut histogram = [0u64; 256];
+    for &b in segment {
+        histogram[b as usize] += 1;
+    }
+    let total = segment.len() as u64;
+    let entropy = shannon_entropy(&histogram, total);
+    let chi = chi_square(&histogram, total);
+    (entropy, chi, entropy >= MIN_ENTROPY_BITS && chi <= CHI_SQUARE_LIMIT)
+}
+
+/// Offsets of the samples: the device is split into equal strata and one
+/// block is picked at random in each, so the whole device is covered and
+/// the first and last strata are always included.
+pub fn sample_offsets(size: u64, coverage_percent: f64, rng: &mut StdRng) -> Vec<u64> {
+    let blocks = size.div_ceil(SAMPLE_SIZE);
+    let wanted = (size as f64 * coverage_percent / 100.0 / SAMPLE_SIZE as f64).ceil() as u64;
+    let count = wanted.max(MIN_SAMPLES).min(blocks);
+    (0..count)
+        .map(|i| {
+            let first = i * blocks / count;
+            let last = ((i + 1) * blocks / count).max(first + 1);
+            rng.gen_range(first..last) * SAMPLE_SIZE
+        })
+        .collect()
+}
+
+/// Read pseudo-randomly chosen samples from `target` and check each part of
+/// them against what the plan expects there. The seed makes the sample
+/// selection reproducible.
+pub fn verify_samples(target: &str, plan: &VerifyPlan, seed: u64) -> io::Result<VerifyOutcome> {
+    let mut device = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(target)?;
+    let size = device.seek(SeekFrom::End(0))?;
+    let geometry = geometry::read_geometry(target).unwrap_or_default();
+    let mut buffer = AlignedBuffer::zeroed(SAMPLE_SIZE as usize, geometry.logical as usize);
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let offsets = sample_offsets(size, plan.coverage_percent, &mut rng);
+    let mut outcome = VerifyOutcome {
+        seed,
+        samples: offsets.len() as u64,
+        skipped_samples: 0,
+        bytes_read: 0,
+        target_size: size,
+        min_entropy: None,
+        max_chi_square: None,
+        failures: Vec::new(),
+        failed_samples: 0,
+    };
+
+    for (i, &offset) in offsets.iter().enumerate() {
+        let len = SAMPLE_SIZE.min(size - offset);
+        let sample = ByteRange { start: offset, end: offset + len };
+        if plan.skip.iter().any(|bad| bad.start < sample.end && sample.start < bad.end) {
+            outcome.skipped_samples += 1;
+            continue;
+        }
+
+        let data = &mut buffer.as_mut_slice()[..len as usize];
+        read_sample(&device, data, offset)?;
+        outcome.bytes_read += len;
+
+        if let Some(failure) = check_sample(data, &sample, plan, &mut outcome) {
+            outcome.failed_samples += 1;
+            if outcome.failures.len() < MAX_REPORTED_FAILURES {
+                outcome.failures.push(failure);
+            }
+        }
+
+        if i % 16 == 0 || i + 1 == offsets.len() {
+            print!("\r   Verified {} / {} samples ({} failed)   ", i + 1, offsets.len(), outcome.failed_samples);
+            io::stdout().flush()?;
+        }
+    }
+    println!();
+    Ok(outcome)
+}
+
+pub fn read_sample(device: &File, data: &mut [u8], offset: u64) -> io::Result<()> {
+    let mut done = 0;
+    while done < data.len() {
+        match device.read_at(&mut data[done..], offset + done as u64)? {
+            0 => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Device ended during verification")),
+            n => done += n,
+        }
+    }
+    Ok(())
+}
+
+fn check_sample(data: &[u8], sample: &ByteRange, plan: &VerifyPlan, outcome: &mut VerifyOutcome) -> Option<SampleFailure> {
+    for (region, expected) in &plan.regions {
+        let start = region.start.max(sample.start);
+        let end = region.end.min(sample.end);
+        if start >= end {
+            continue;
+        }
+        let segment = &data[(start - sample.start) as usize..(end - sample.start) as usize];
+        let failure = |detail: String| Some(SampleFailure { offset: start + plan.device_offset, expected: *expected, detail });
+
+        match expected {
+            Expected::Fill(byte) => {
+                if let Some(position) = segment.iter().position(|b| b != byte) {
+                    return failure(format!("byte 0x{:02x} at +{} where 0x{:02x} was written",
+                        segment[position], position, byte));
+                }
+            }
+            Expected::Random if segment.len() >= MIN_RANDOM_SEGMENT => {
+                let (entropy, chi, random) = randomness(segment);
+                outcome.min_entropy = Some(outcome.min_entropy.map_or(entropy, |e| e.min(entropy)));
+                outcome.max_chi_square = Some(outcome.max_chi_square.map_or(chi, |c| c.max(chi)));
+                if !random {
+                    return failure(format!("entropy {:.4} bits/byte, chi-square {:.1} - not random", entropy, chi));
+                }
+            }
+            Expected::Random => {}
+        }
+    }
+    None
+}
+
+/// Result of reading the whole target back. Ranges are in device offsets.
+#[derive(Debug, Clone)]
+pub struct FullVerifyOutcome {
+    pub pattern: Pattern,
+    pub target_size: u64,
+    pub bytes_read: u64,
+    pub bytes_skipped: u64,
+    pub duration: Duration,
+    pub mismatched_bytes: u64,
+    pub mismatches: Vec<ByteRange>,
+    pub unreadable: Vec<ByteRange>,
+}
+
+impl FullVerifyOutcome {
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty() && self.unreadable.is_empty()
+    }
+
+    pub fn describe(&self) -> String {
+        let secs = self.duration.as_secs_f64().max(0.001);
+        let mut summary = format!("{} of {} bytes read back in {:.1}s ({:.1} MB/s) and compared with the {}; \
+                                   {} bytes in {} ranges differ",
+            self.bytes_read, self.target_size, secs, self.bytes_read as f64 / secs / 1_000_000.0,
+            self.pattern.describe(), self.mismatched_bytes, self.mismatches.len());
+        if !self.unreadable.is_empty() {
+            let bad: u64 = self.unreadable.iter().map(ByteRange::len).sum();
+            summary.push_str(&format!(", {} bytes in {} ranges unreadable", bad, self.unreadable.len()));
+        }
+        if self.bytes_skipped > 0 {
+            summary.push_str(&format!(", {} bytes on unwritable sectors skipped", self.bytes_skipped));
+        }
+        summary
+    }
+}
+
+fn push_range(ranges: &mut Vec<ByteRange>, start: u64, len: u64) {
+    if let Some(last) = ranges.last_mut() {
+        if last.end == start {
+            last.end += len;
+            return;
+        }
+    }
+    ranges.push(ByteRange { start, end: start + len });
+}
+
+/// Compare one block sector by sector and record which sectors differ.
+fn compare_sectors(data: &[u8], expected: &[u8], offset: u64, sector: usize, skip: &[ByteRange],
+                   device_offset: u64, outcome: &mut FullVerifyOutcome) {
+    for (i, (got, want)) in data.chunks(sector).zip(expected.chunks(sector)).enumerate() {
+        let start = offset + (i * sector) as u64;
+        let end = start + got.len() as u64;
+        if skip.iter().any(|bad| bad.start < end && start < bad.end) {
+            outcome.bytes_skipped += got.len() as u64;
+            continue;
+        }
+        if got != want {
+            outcome.mismatched_bytes += got.iter().zip(want).filter(|(a, b)| a != b).count() as u64;
+            push_range(&mut outcome.mismatches, device_offset + start, got.len() as u64);
+        }
+    }
+}
+
+/// Read every byte of `target` and compare it with `pattern`, regenerating
+/// random streams from their seed. Sectors in `skip` (target offsets, i.e.
+/// the defects of the write pass) are not compared; sectors that cannot be
+/// read are reported separately from sectors holding the wrong data.
+pub fn verify_full(target: &str, pattern: &Pattern, skip: &[ByteRange], device_offset: u64) -> io::Result<FullVerifyOutcome> {
+    let mut device = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(target)?;
+    let size = device.seek(SeekFrom::End(0))?;
+    let geometry = geometry::read_geometry(target).unwrap_or_default();
+    let sector = geometry.logical as usize;
+    let block_size = geometry.io_size(FULL_VERIFY_BLOCK) as usize;
+    let mut buffer = AlignedBuffer::zeroed(block_size, sector);
+    let mut expected = vec![0u8; block_size];
+    if pattern.is_constant() {
+        pattern.fill(&mut expected, 0);
+    }
+
+    let mut outcome = FullVerifyOutcome {
+        pattern: *pattern,
+        target_size: size,
+        bytes_read: 0,
+        bytes_skipped: 0,
+        duration: Duration::ZERO,
+        mismatched_bytes: 0,
+        mismatches: Vec::new(),
+        unreadable: Vec::new(),
+    };
+    let started = Instant::now();
+    let mut last_progress = Instant::now();
+    let mut offset = 0;
+
+    while offset < size {
+        let len = ((size - offset) as usize).min(block_size);
+        if !pattern.is_constant() {
+            pattern.fill(&mut expected[..len], offset);
+        }
+        let data = &mut buffer.as_mut_slice()[..len];
+        let block = ByteRange { start: offset, end: offset + len as u64 };
+        let clean = !skip.iter().any(|bad| bad.start < block.end && block.start < bad.end);
+
+        match read_sample(&device, data, offset) {
+            Ok(()) => {
+                outcome.bytes_read += len as u64;
+                // Fast path: a whole block that matches needs no sector-level look
+                if !clean || data[..] != expected[..len] {
+                    compare_sectors(data, &expected[..len], offset, sector, skip, device_offset, &mut outcome);
+                }
+            }
+            Err(e) if e.raw_os_error() == Some(libc::EIO) => {
+                // Narrow a media error down to the sectors that cannot be read
+                for (i, part) in data.chunks_mut(sector).enumerate() {
+                    let start = offset + (i * sector) as u64;
+                    let want = &expected[i * sector..i * sector + part.len()];
+                    let end = start + part.len() as u64;
+                    if skip.iter().any(|bad| bad.start < end && start < bad.end) {
+                        outcome.bytes_skipped += part.len() as u64;
+                        continue;
+                    }
+                    match read_sample(&device, part, start) {
+                        Ok(()) => {
+                            outcome.bytes_read += part.len() as u64;
+                            compare_sectors(part, want, start, sector, skip, device_offset, &mut outcome);
+                        }
+                        Err(e) if e.raw_os_error() == Some(libc::EIO) => {
+                            push_range(&mut outcome.unreadable, device_offset + start, part.len() as u64)
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+            Err(e) => return Err(e),
+        }
+        offset += len as u64;
+
+        if last_progress.elapsed() >= Duration::from_secs(1) || offset == size {
+            print!("\r   Read back {:.2} / {:.2} GiB ({} bytes differ)   ",
+                offset as f64 / (1u64 << 30) as f64, size as f64 / (1u64 << 30) as f64, outcome.mismatched_bytes);
+            io::stdout().flush()?;
+            last_progress = Instant::now();
+        }
+    }
+    println!();
+
+    outcome.duration = started.elapsed();
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn sample_offsets_cover_the_device_reproducibly() {
+        let size = 100 << 30;
+        let offsets = sample_offsets(size, DEFAULT_COVERAGE, &mut StdRng::seed_from_u64(42));
+        assert_eq!(offsets, sample_offsets(size, DEFAULT_COVERAGE, &mut StdRng::seed_from_u64(42)));
+        assert_ne!(offsets, sample_offsets(size, DEFAULT_COVERAGE, &mut StdRng::seed_from_u64(43)));
+
+        // 1% of 100 GiB in 1 MiB samples, one per stratum
+        assert_eq!(offsets.len(), 1024);
+        assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(offsets.iter().all(|&offset| offset % SAMPLE_SIZE == 0 && offset < size));
+        let stratum = size / offsets.len() as u64;
+        assert!(offsets[0] < stratum + SAMPLE_SIZE);
+        assert!(*offsets.last().unwrap() >= size - stratum - SAMPLE_SIZE);
+    }
+
+    #[test]
+    fn sample_offsets_respect_the_minimum_and_small_devices() {
+        // Low coverage is raised to the minimum sample count
+        let size = 1 << 30;
+        let offsets = sample_offsets(size, 0.01, &mut StdRng::seed_from_u64(7));
+        assert_eq!(offsets.len() as u64, MIN_SAMPLES);
+        assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(offsets.iter().all(|&offset| offset % SAMPLE_SIZE == 0 && offset < size));
+
+        // A device smaller than the minimum is read in full, including a
+        // partial last block
+        let size = 50 * SAMPLE_SIZE + 4096;
+        let offsets = sample_offsets(size, DEFAULT_COVERAGE, &mut StdRng::seed_from_u64(7));
+        assert_eq!(offsets, (0..51).map(|i| i * SAMPLE_SIZE).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn randomness_accepts_ciphertext_and_rejects_structure() {
+        let mut random = vec![0u8; SAMPLE_SIZE as usize];
+        ChaCha20Rng::from_seed([5u8; 32]).fill_bytes(&mut random);
+        let (entropy, chi, passed) = randomness(&random);
+        assert!(passed, "entropy {} chi-square {}", entropy, chi);
+        assert!(entropy >= MIN_ENTROPY_BITS && chi <= CHI_SQUARE_LIMIT);
+
+        let (entropy, _, passed) = randomness(&vec![0u8; SAMPLE_SIZE as usize]);
+        assert!(!passed);
+        assert_eq!(entropy, 0.0);
+
+        // Zeroes in every 64th byte skew the histogram
+        let mut skewed = random.clone();
+        for byte in skewed.iter_mut().step_by(64) {
+            *byte = 0;
+        }
+        let (_, chi, passed) = randomness(&skewed);
+        assert!(!passed, "chi-square {}", chi);
+    }
+
+    #[test]
+    fn fill_regions_are_checked_exactly() {
+        let plan = VerifyPlan {
+            regions: vec![(ByteRange { start: 0, end: 8192 }, Expected::Fill(0xff))],
+            skip: Vec::new(),
+            coverage_percent: DEFAULT_COVERAGE,
+            device_offset: 1 << 20,
+        };
+        let sample = ByteRange { start: 4096, end: 8192 };
+        let mut outcome = empty_outcome();
+        let mut data = vec![0xffu8; 4096];
+        assert!(check_sample(&data, &sample, &plan, &mut outcome).is_none());
+
+        data[100] = 0xfe;
+        let failure = check_sample(&data, &sample, &plan, &mut outcome).unwrap();
+        assert_eq!(failure.offset, (1 << 20) + 4096);
+        assert_eq!(failure.detail, "byte 0xfe at +100 where 0xff was written");
+    }
+
+    fn empty_outcome() -> VerifyOutcome {
+        VerifyOutcome {
+            seed: 0,
+            samples: 0,
+            skipped_samples: 0,
+            bytes_read: 0,
+            target_size: 0,
+            min_entropy: None,
+            max_chi_square: None,
+            failures: Vec::new(),
+            failed_samples: 0,
+        }
+    }
+}
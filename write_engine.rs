@@ -0,0 +1,308 @@
+use std::alloc::{self, Layout};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::os::unix::fs::{FileExt, MetadataExt, OpenOptionsExt};
+use std::time::{Duration, Instant};
+use crate::geometry;
+use crate::pattern::Pattern;
+use crate::range::ByteRange;
+use crate::throttle::Throttle;
+
+const BLOCK_SIZE: usize = 8 * 1024 * 1024;
+
+// I/O sizes tried in turn when a write fails, finishing with a single
+// logical sector of the target
+const RETRY_SIZES: &[usize] = &[1024 * 1024, 64 * 1024, 4096];
+const DIRECT_IO_ALIGN: usize = 4096;
+const EIO: i32 = 5;
+
+// A drive that fails this many bytes in a row, or this many in total, is
+// treated as dead or disconnected rather than as having bad sectors
+const MAX_CONSECUTIVE_UNWRITABLE: u64 = 16 * 1024 * 1024;
+const MAX_UNWRITABLE: u64 = 1024 * 1024 * 1024;
+
+// How much data may be written between two durable checkpoints
+const CHECKPOINT_INTERVAL: u64 = 1024 * 1024 * 1024;
+
+/// Zero-filled buffer aligned for O_DIRECT.
+pub struct AlignedBuffer {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+impl AlignedBuffer {
+    pub fn zeroed(len: usize, align: usize) -> Self {
+        let layout = Layout::from_size_align(len, align.max(DIRECT_IO_ALIGN)).unwrap();
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        AlignedBuffer { ptr, layout }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.layout.size()) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.layout.size()) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        unsafe { alloc::dealloc(self.ptr, self.layout) }
+    }
+}
+
+fn print_progress(offset: u64, start: u64, total: u64, started: Instant) -> io::Result<()> {
+    let elapsed = started.elapsed().as_secs_f64().max(0.001);
+    let percent = if total > 0 { offset as f64 * 100.0 / total as f64 } else { 100.0 };
+    print!("\r   {:.2} / {:.2} GiB ({:.1}%) - {:.1} MB/s   ",
+        offset as f64 / (1u64 << 30) as f64,
+        total as f64 / (1u64 << 30) as f64,
+        percent,
+        (offset - start) as f64 / elapsed / 1_000_000.0);
+    io::stdout().flush()
+}
+
+fn record_defect(defects: &mut Vec<ByteRange>, start: u64, len: u64) {
+    if let Some(last) = defects.last_mut() {
+        if last.end == start {
+            last.end += len;
+            return;
+        }
+    }
+    defects.push(ByteRange { start, end: start + len });
+}
+
+/// Tells scattered bad sectors, which are skipped and recorded, from a drive
+/// that has died or dropped off the bus, which often also answers with EIO.
+struct FailureGuard {
+    /// sysfs directory of the target block device
+    sysfs: Option<std::path::PathBuf>,
+    consecutive: u64,
+    total: u64,
+}
+
+impl FailureGuard {
+    fn new(device: &File) -> Self {
+        let sysfs = device.metadata().ok().and_then(|meta| {
+            let rdev = meta.rdev();
+            std::fs::canonicalize(format!("/sys/dev/block/{}:{}", libc::major(rdev), libc::minor(rdev))).ok()
+        });
+        FailureGuard { sysfs, consecutive: 0, total: 0 }
+    }
+
+    fn written(&mut self) {
+        self.consecutive = 0;
+    }
+
+    /// Whether the device node is gone or the kernel has taken the drive
+    /// offline (SCSI/SATA/USB disks report their state in sysfs).
+    fn device_gone(&self) -> bool {
+        let Some(sysfs) = &self.sysfs else { return false };
+        if !sysfs.exists() {
+            return true;
+        }
+        let disk = if sysfs.join("partition").exists() { sysfs.parent().unwrap_or(sysfs) } else { sysfs.as_path() };
+        match std::fs::read_to_string(disk.join("device/state")) {
+            Ok(state) => state.trim() != "running" && state.trim() != "live",
+            Err(_) => false,
+        }
+    }
+
+    fn unwritable(&mut self, offset: u64, len: u64) -> io::Result<()> {
+        self.consecutive += len;
+        self.total += len;
+        if self.device_gone() {
+            return Err(io::Error::other(format!(
+                "The device disappeared or went offline at byte {} - aborting instead of recording the rest as bad sectors",
+                offset
+            )));
+        }
+        if self.consecutive >= MAX_CONSECUTIVE_UNWRITABLE {
+            return Err(io::Error::other(format!(
+                "{} consecutive bytes up to byte {} could not be written - the drive is failing or disconnected",
+                self.consecutive, offset + len
+            )));
+        }
+        if self.total >= MAX_UNWRITABLE {
+            return Err(io::Error::other(format!(
+                "{} bytes could not be written - too many bad sectors to continue", self.total
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The open target and how failed writes to it are retried.
+struct WriteTarget {
+    device: File,
+    /// I/O sizes to retry a failed block with, down to one logical sector
+    retry_sizes: Vec<usize>,
+    /// Offset of the target on the physical device, for defect ranges
+    device_offset: u64,
+}
+
+/// Write the first `len` bytes of `buffer` at `offset`. A media error makes the block be retried in
+/// smaller pieces; a single sector that still fails is recorded in `defects`
+/// (as `device_offset + offset`) and skipped, unless `guard` decides the drive
+/// is gone. Other errors abort the wipe.
+fn write_block(
+    target: &WriteTarget,
+    buffer: &[u8],
+    offset: u64,
+    len: usize,
+    defects: &mut Vec<ByteRange>,
+    guard: &mut FailureGuard,
+) -> io::Result<()> {
+    let device_offset = target.device_offset;
+    let error = match target.device.write_all_at(&buffer[..len], offset) {
+        Ok(()) => {
+            guard.written();
+            return Ok(());
+        }
+        Err(e) if e.raw_os_error() == Some(EIO) => e,
+        Err(e) if matches!(e.raw_os_error(), Some(libc::ENODEV) | Some(libc::ENXIO)) => {
+            return Err(io::Error::other(format!("The device disappeared at byte {}: {}", device_offset + offset, e)));
+        }
+        Err(e) => return Err(e),
+    };
+
+    let retry_size = match target.retry_sizes.iter().find(|&&size| size < len) {
+        Some(&size) => size,
+        None => {
+            println!("\n⚠️  Unwritable sector at byte {} ({})", device_offset + offset, error);
+            record_defect(defects, device_offset + offset, len as u64);
+            return guard.unwritable(device_offset + offset, len as u64);
+        }
+    };
+
+    let mut chunk = offset;
+    let end = offset + len as u64;
+    while chunk < end {
+        let chunk_len = ((end - chunk) as usize).min(retry_size);
+        let part = &buffer[(chunk - offset) as usize..];
+        write_block(target, part, chunk, chunk_len, defects, guard)?;
+        chunk += chunk_len as u64;
+    }
+    Ok(())
+}
+
+/// Write `pattern` from `start` to the end of `target`, bypassing the page cache
+/// so every failed write is seen. Block size, buffer alignment and retry
+/// granularity follow the target's sector geometry. Failing blocks are retried
+/// with smaller I/O sizes; sectors that cannot be written are skipped and appended to
+/// `defects`, expressed as byte ranges of the physical device (`target` starts
+/// at `device_offset` on it).
+///
+/// After every CHECKPOINT_INTERVAL the data is flushed to the device and
+/// `on_checkpoint` is called with the offset up to which the write is known to
+/// be durable and the defects found so far. Writes are paced by `throttle`.
+/// Returns the total size of the target.
+pub fn fill_pattern<F>(
+    target: &str,
+    start: u64,
+    device_offset: u64,
+    pattern: &Pattern,
+    throttle: &mut Throttle,
+    defects: &mut Vec<ByteRange>,
+    mut on_checkpoint: F,
+) -> io::Result<u64>
+where
+    F: FnMut(u64, &[ByteRange]) -> io::Result<()>,
+{
+    let mut device = OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(target)?;
+    let total = device.seek(SeekFrom::End(0))?;
+    let geometry = geometry::read_geometry(target).unwrap_or_default();
+    let sector = geometry.logical as usize;
+    let block_size = geometry.io_size(BLOCK_SIZE as u64) as usize;
+    let retry_sizes: Vec<usize> = RETRY_SIZES
+        .iter()
+        .copied()
+        .filter(|&size| size > sector && size % sector == 0)
+        .chain(std::iter::once(sector))
+        .collect();
+
+    let start = start.min(total) - start.min(total) % sector as u64;
+    let mut offset = start;
+
+    let mut buffer = AlignedBuffer::zeroed(block_size, sector);
+    if pattern.is_constant() {
+        pattern.fill(buffer.as_mut_slice(), 0);
+    }
+    let started = Instant::now();
+    let mut last_progress = Instant::now();
+    let mut last_checkpoint = offset;
+    let defects_before = defects.len();
+    let mut guard = FailureGuard::new(&device);
+    let writer = WriteTarget { device, retry_sizes, device_offset };
+
+    while offset < total {
+        let len = ((total - offset) as usize).min(block_size);
+        if !pattern.is_constant() {
+            pattern.fill(&mut buffer.as_mut_slice()[..len], offset);
+        }
+        write_block(&writer, buffer.as_slice(), offset, len, defects, &mut guard)?;
+        offset += len as u64;
+        throttle.pace(len);
+
+        if offset - last_checkpoint >= CHECKPOINT_INTERVAL {
+            writer.device.sync_data()?;
+            on_checkpoint(offset, defects)?;
+            last_checkpoint = offset;
+        }
+
+        if last_progress.elapsed() >= Duration::from_secs(1) {
+            print_progress(offset, start, total, started)?;
+            last_progress = Instant::now();
+        }
+    }
+
+    writer.device.sync_data()?;
+    on_checkpoint(offset, defects)?;
+    print_progress(offset, start, total, started)?;
+    println!();
+
+    if defects.len() > defects_before {
+        let bad: u64 = defects.iter().map(ByteRange::len).sum();
+        println!("⚠️  {} bytes in {} ranges could not be written", bad, defects.len());
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failure_guard_stops_on_a_dead_drive() {
+        let sector = 4096;
+        let mut guard = FailureGuard { sysfs: None, consecutive: 0, total: 0 };
+
+        // Scattered bad sectors are tolerated
+        for i in 0..100 {
+            guard.unwritable(i * 1_000_000, sector).unwrap();
+            guard.written();
+        }
+
+        // A run of failures without a single successful write is not
+        let failed_at = (0..).find(|&i| guard.unwritable(i * sector, sector).is_err()).unwrap();
+        assert_eq!((failed_at + 1) * sector, MAX_CONSECUTIVE_UNWRITABLE);
+
+        // Nor is a total beyond the budget, however it is spread out
+        let mut guard = FailureGuard { sysfs: None, consecutive: 0, total: 0 };
+        let chunk = MAX_CONSECUTIVE_UNWRITABLE / 2;
+        let failed_at = (0..).find(|&i| {
+            let result = guard.unwritable(i * chunk * 4, chunk);
+            guard.written();
+            result.is_err()
+        }).unwrap();
+        assert_eq!((failed_at + 1) * chunk, MAX_UNWRITABLE);
+    }
+}